@@ -45,12 +45,22 @@ local function generateRectangles(n)
 	return sorter
 end
 
-local function fetchSVG(file)
+local function fetchSVG(path)
 	local sorter = Sorter()
-	local file = assert(io.open(file, "r"))
+	local file = assert(io.open(path, "r"))
 	local svg = file:read("*all")
 	
-	local rects = parser:parse(svg)
+	local format = path:lower():match("%.(%w+)$")
+	if format ~= 'dxf' and format ~= 'json' then
+		format = 'svg'
+	end
+	local rects, drawing, warnings = parser:parse(svg, format)
+	for _, warning in ipairs(warnings) do
+		print("Warning: " .. warning)
+	end
+	sorter.drawing = drawing
+	sorter.format  = format
+	nesting = nil
 
 	for _, rect in pairs(rects) do
 		local shape = Movable(rect)
@@ -125,5 +135,31 @@ function love.keypressed(key, scancode, isrepeat)
 		end
 
 		cycle = cycle + 1
+	elseif key == "n" and sorter.drawing then
+		local width, height = love.graphics.getDimensions()
+		height = (height - 30) / 2
+		nesting = parser.nest(sorter.drawing, width - 20, height, 2)
+		sorter:nest(nesting, height)
+	elseif key == "e" and nesting then
+		-- Unités CSS, comme Inkscape, vers millimètres ; le DXF et le JSON sont
+		-- déjà lus en millimètres
+		local scale = sorter.format == 'svg' and 25.4 / 96 or 1
+		for sheet=0, nesting.sheets-1 do
+			for _, format in ipairs({'svg', 'dxf', 'gcode'}) do
+				local text = parser.write(sorter.drawing, nesting, sheet, format, scale, sorter.format ~= 'dxf')
+				-- Un format qui n'a pu être écrit est passé
+				if text then
+					local file = assert(io.open(("sheet%d.%s"):format(sheet + 1, format), "w"))
+					file:write(text)
+					file:close()
+				end
+			end
+		end
+		local text = parser.json(sorter.drawing, nesting, scale)
+		if text then
+			local file = assert(io.open("layout.json", "w"))
+			file:write(text)
+			file:close()
+		end
 	end
 end
@@ -0,0 +1,691 @@
+use std::f32::consts::PI;
+use path::{Point, Polygon};
+
+/// A piece of outline, running from the end of the previous one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Line(Point),
+    /// Arc around `center`, turning `sweep` radians, counter-clockwise if
+    /// positive (in a Y-up coordinate system)
+    Arc { end: Point, center: Point, sweep: f32 },
+}
+
+impl Segment {
+    pub fn end(&self) -> Point {
+        match *self {
+            Segment::Line(end) | Segment::Arc { end, .. } => end,
+        }
+    }
+
+    /// Length of the segment starting at `start`
+    pub fn length(&self, start: Point) -> f32 {
+        match *self {
+            Segment::Line(end) => distance(start, end),
+            Segment::Arc { center, sweep, .. } => distance(center, start) * sweep.abs(),
+        }
+    }
+
+    /// The point at the fraction `t` of the segment starting at `start`
+    pub fn point(&self, start: Point, t: f32) -> Point {
+        match *self {
+            Segment::Line(end) => Point(start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t),
+            Segment::Arc { center, sweep, .. } => {
+                let (sin, cos) = (sweep * t).sin_cos();
+                let (x, y) = (start.0 - center.0, start.1 - center.1);
+                Point(center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+            }
+        }
+    }
+
+    /// Unit vector of the direction of the segment starting at `start`, at
+    /// the fraction `t` of it
+    pub fn direction(&self, start: Point, t: f32) -> Point {
+        let (x, y) = match *self {
+            Segment::Line(end) => (end.0 - start.0, end.1 - start.1),
+            Segment::Arc { center, sweep, .. } => {
+                let p = self.point(start, t);
+                let (x, y) = (p.0 - center.0, p.1 - center.1);
+                (-y * sweep.signum(), x * sweep.signum())
+            }
+        };
+        let length = x.hypot(y);
+        if length == 0. {
+            Point(0., 0.)
+        } else {
+            Point(x / length, y / length)
+        }
+    }
+
+    /// Tangent of a quarter of the sweep, as used by DXF polylines
+    pub fn bulge(&self) -> f32 {
+        match *self {
+            Segment::Line(_) => 0.,
+            Segment::Arc { sweep, .. } => (sweep / 4.).tan(),
+        }
+    }
+
+    /// Whether the segment is a whole circle, ending where it starts
+    pub fn is_circle(&self) -> bool {
+        match *self {
+            Segment::Arc { sweep, .. } => sweep.abs() >= 2. * PI - 1e-3,
+            _ => false,
+        }
+    }
+}
+
+/// An outline made of lines and arcs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    pub start: Point,
+    pub segments: Vec<Segment>,
+    /// Whether the last segment ends at the start
+    pub closed: bool,
+}
+
+impl Curve {
+    /// Where the last segment ends
+    pub fn end(&self) -> Point {
+        self.segments.last().map_or(self.start, |s| s.end())
+    }
+
+    pub fn length(&self) -> f32 {
+        let mut current = self.start;
+        let mut length = 0.;
+        for segment in &self.segments {
+            length += segment.length(current);
+            current = segment.end();
+        }
+        length
+    }
+
+    /// Area enclosed by a closed curve, positive if it turns counter-clockwise
+    pub fn signed_area(&self) -> f32 {
+        let mut current = self.start;
+        let mut area = 0.;
+        for segment in &self.segments {
+            let end = segment.end();
+            area += (current.0 * end.1 - end.0 * current.1) / 2.;
+            // The circular segment between the arc and its chord
+            if let Segment::Arc { center, sweep, .. } = *segment {
+                let radius = distance(center, current);
+                area += sweep.signum() * radius * radius / 2. * (sweep.abs() - sweep.abs().sin());
+            }
+            current = end;
+        }
+        area
+    }
+
+    /// The open curve running along this one from the length `from` to the
+    /// length `to`
+    pub fn slice(&self, from: f32, to: f32) -> Curve {
+        let mut current = self.start;
+        let mut length = 0.;
+        let mut start = None;
+        let mut segments = Vec::new();
+        for segment in &self.segments {
+            let l = segment.length(current);
+            if l > 0. && length + l > from && length < to {
+                let (t0, t1) = (((from - length) / l).max(0.), ((to - length) / l).min(1.));
+                start = start.or_else(|| Some(segment.point(current, t0)));
+                let end = segment.point(current, t1);
+                segments.push(match *segment {
+                    Segment::Line(_) => Segment::Line(end),
+                    Segment::Arc { center, sweep, .. } => Segment::Arc {
+                        end,
+                        center,
+                        sweep: sweep * (t1 - t0),
+                    },
+                });
+            }
+            length += l;
+            current = segment.end();
+        }
+
+        Curve {
+            start: start.unwrap_or(if from <= 0. { self.start } else { current }),
+            segments,
+            closed: false,
+        }
+    }
+
+    /// The same outline, run the other way
+    pub fn reverse(&self) -> Curve {
+        let mut segments = Vec::with_capacity(self.segments.len());
+        let mut end = self.start;
+        for segment in &self.segments {
+            segments.push(match *segment {
+                Segment::Line(_) => Segment::Line(end),
+                Segment::Arc { center, sweep, .. } => Segment::Arc {
+                    end,
+                    center,
+                    sweep: -sweep,
+                },
+            });
+            end = segment.end();
+        }
+        segments.reverse();
+        Curve {
+            start: end,
+            segments,
+            closed: self.closed,
+        }
+    }
+}
+
+impl From<&Polygon> for Curve {
+    /// The edges of the polygon, as lines
+    fn from(polygon: &Polygon) -> Self {
+        let start = polygon.points.first().cloned().unwrap_or(Point(0., 0.));
+        let mut segments: Vec<_> = polygon.points.iter().skip(1).map(|&p| Segment::Line(p)).collect();
+        if polygon.closed {
+            segments.push(Segment::Line(start));
+        }
+        Curve {
+            start,
+            segments,
+            closed: polygon.closed,
+        }
+    }
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+/// Center of the circle through three points, unless they are aligned
+fn circumcenter(a: Point, b: Point, c: Point) -> Option<Point> {
+    let (ax, ay) = (f64::from(a.0), f64::from(a.1));
+    let (bx, by) = (f64::from(b.0) - ax, f64::from(b.1) - ay);
+    let (cx, cy) = (f64::from(c.0) - ax, f64::from(c.1) - ay);
+    let d = 2. * (bx * cy - by * cx);
+    if d.abs() < 1e-12 * (bx * bx + by * by + cx * cx + cy * cy) || d == 0. {
+        return None;
+    }
+
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let x = (cy * b2 - by * c2) / d;
+    let y = (bx * c2 - cx * b2) / d;
+    Some(Point((ax + x) as f32, (ay + y) as f32))
+}
+
+/// Signed angle from `a` to `b` around `center`
+fn angle(center: Point, a: Point, b: Point) -> f32 {
+    let (u, v) = (Point(a.0 - center.0, a.1 - center.1), Point(b.0 - center.0, b.1 - center.1));
+    (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1)
+}
+
+/// The sweep of the arc around `center` through the points, if the points
+/// and the middles of the edges joining them all lie within `tolerance` of
+/// the circle, turning the same way
+fn sweep(points: &[Point], center: Point, tolerance: f32) -> Option<f32> {
+    let radius = distance(center, points[0]);
+    let on_circle = |p: Point| (distance(center, p) - radius).abs() <= tolerance;
+
+    let mut sweep = 0.;
+    for pair in points.windows(2) {
+        let middle = Point((pair[0].0 + pair[1].0) / 2., (pair[0].1 + pair[1].1) / 2.);
+        let step = angle(center, pair[0], pair[1]);
+        if !on_circle(pair[1]) || !on_circle(middle) || step == 0. || (sweep != 0. && step.signum() != f32::signum(sweep)) {
+            return None;
+        }
+        sweep += step;
+    }
+    Some(sweep)
+}
+
+/// The longest arc starting at the first point, spanning three edges or more
+fn longest_arc(points: &[Point], tolerance: f32) -> Option<(usize, Point, f32)> {
+    let mut found = None;
+    for j in 3..points.len() {
+        let center = match circumcenter(points[0], points[j / 2], points[j]) {
+            Some(center) => center,
+            None => break,
+        };
+        let sweep = match sweep(&points[..(j + 1)], center, tolerance) {
+            Some(sweep) if sweep.abs() < 2. * PI - 1e-3 => sweep,
+            _ => break,
+        };
+
+        // Arcs too flat to stand out from their chord are left as lines
+        let radius = distance(center, points[0]);
+        if radius * (1. - (sweep / 2.).cos()) > tolerance {
+            found = Some((j, center, sweep));
+        }
+    }
+    found
+}
+
+/// Distance from `p` to the segment starting at `start`
+fn segment_distance(start: Point, segment: &Segment, p: Point) -> f32 {
+    match *segment {
+        Segment::Line(end) => {
+            let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+            let length = dx * dx + dy * dy;
+            if length == 0. {
+                return distance(start, p);
+            }
+            let t = (((p.0 - start.0) * dx + (p.1 - start.1) * dy) / length).clamp(0., 1.);
+            distance(p, Point(start.0 + dx * t, start.1 + dy * t))
+        }
+        Segment::Arc { end, center, sweep } => {
+            let mut a = angle(center, start, p);
+            if a * sweep < 0. {
+                a += 2. * PI * sweep.signum();
+            }
+            if a.abs() <= sweep.abs() {
+                (distance(center, p) - distance(center, start)).abs()
+            } else {
+                distance(p, start).min(distance(p, end))
+            }
+        }
+    }
+}
+
+/// Unit vector of the direction, at `a`, of the curve through the points
+/// `a`, `b` and `c`, taken from the circle through them
+fn tangent(a: Point, b: Point, c: Point) -> Option<Point> {
+    let (x, y) = match circumcenter(a, b, c) {
+        Some(center) => {
+            let (x, y) = (center.1 - a.1, a.0 - center.0);
+            if x * (b.0 - a.0) + y * (b.1 - a.1) < 0. {
+                (-x, -y)
+            } else {
+                (x, y)
+            }
+        }
+        None => (b.0 - a.0, b.1 - a.1),
+    };
+    let length = x.hypot(y);
+    if length == 0. {
+        None
+    } else {
+        Some(Point(x / length, y / length))
+    }
+}
+
+/// The arc from `a` to `b` leaving `a` towards `direction`, or the line
+/// between them when the arc would stray from it by less than `tolerance`
+fn tangent_arc(a: Point, direction: Point, b: Point, tolerance: f32) -> Segment {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let normal = Point(-direction.1, direction.0);
+    let offset = normal.0 * dx + normal.1 * dy;
+    if offset == 0. {
+        return Segment::Line(b);
+    }
+
+    // Signed radius, positive when turning counter-clockwise
+    let radius = (dx * dx + dy * dy) / (2. * offset);
+    let center = Point(a.0 + normal.0 * radius, a.1 + normal.1 * radius);
+    let mut sweep = angle(center, a, b);
+    if sweep * radius < 0. {
+        sweep += 2. * PI * radius.signum();
+    }
+    if radius.abs() * (1. - (sweep / 2.).cos()) <= tolerance / 2. {
+        Segment::Line(b)
+    } else {
+        Segment::Arc { end: b, center, sweep }
+    }
+}
+
+/// Two arcs from `a` to `b`, leaving `a` towards `ta` and reaching `b`
+/// towards `tb`, meeting halfway between the points where the tangents of
+/// equal length from `a` and `b` end
+fn biarc(a: Point, ta: Point, b: Point, tb: Point, tolerance: f32) -> Option<(Point, [Segment; 2])> {
+    let v = Point(b.0 - a.0, b.1 - a.1);
+    let t = Point(ta.0 + tb.0, ta.1 + tb.1);
+    let (vt, vv, tt) = (v.0 * t.0 + v.1 * t.1, v.0 * v.0 + v.1 * v.1, t.0 * t.0 + t.1 * t.1);
+
+    // Length of the tangents, from |v - d t| = 2 d
+    let d = if (4. - tt).abs() < 1e-6 {
+        vv / (2. * vt)
+    } else {
+        (vt - (vt * vt - (tt - 4.) * vv).sqrt()) / (tt - 4.)
+    };
+    if !(d.is_finite() && d > 0.) {
+        return None;
+    }
+
+    let (q0, q1) = (Point(a.0 + ta.0 * d, a.1 + ta.1 * d), Point(b.0 - tb.0 * d, b.1 - tb.1 * d));
+    let joint = Point((q0.0 + q1.0) / 2., (q0.1 + q1.1) / 2.);
+    let direction = if q0 == q1 {
+        ta
+    } else {
+        let length = distance(q0, q1);
+        Point((q1.0 - q0.0) / length, (q1.1 - q0.1) / length)
+    };
+    Some((joint, [tangent_arc(a, ta, joint, tolerance), tangent_arc(joint, direction, b, tolerance)]))
+}
+
+/// The longest run of points, from the first one, lying within `tolerance`
+/// of the line joining its ends
+fn longest_line(points: &[Point], tolerance: f32) -> usize {
+    let mut found = 1;
+    for j in 2..points.len() {
+        let line = Segment::Line(points[j]);
+        if points[1..j].iter().any(|&p| segment_distance(points[0], &line, p) > tolerance) {
+            break;
+        }
+        found = j;
+    }
+    found
+}
+
+/// The longest biarc starting at the first point, spanning three edges or
+/// more, which the points and the middles of the edges joining them lie
+/// within `tolerance` of
+fn longest_biarc(points: &[Point], tolerance: f32) -> Option<(usize, [Segment; 2])> {
+    if points.len() < 4 {
+        return None;
+    }
+    let ta = tangent(points[0], points[1], points[2])?;
+
+    let mut found = None;
+    for j in 3..points.len() {
+        let tb = match tangent(points[j], points[j - 1], points[j - 2]) {
+            Some(t) => Point(-t.0, -t.1),
+            None => break,
+        };
+        let (joint, segments) = match biarc(points[0], ta, points[j], tb, tolerance) {
+            Some(biarc) => biarc,
+            None => break,
+        };
+
+        let starts = [points[0], joint];
+        let near = |p: Point| {
+            segments
+                .iter()
+                .zip(&starts)
+                .any(|(segment, &start)| segment_distance(start, segment, p) <= tolerance)
+        };
+        let fits = points[..(j + 1)].windows(2).all(|pair| {
+            near(pair[1]) && near(Point((pair[0].0 + pair[1].0) / 2., (pair[0].1 + pair[1].1) / 2.))
+        });
+        if !fits {
+            break;
+        }
+        found = Some((j, segments));
+    }
+    found
+}
+
+/// Lines and arcs through the points: the runs of points lying on a line or
+/// a circle become a single segment, and the smooth runs, as drawn by
+/// flattened curves, pairs of arcs
+fn segments(points: &[Point], tolerance: f32) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i + 1 < points.len() {
+        let line = longest_line(&points[i..], tolerance);
+        let arc = longest_arc(&points[i..], tolerance).filter(|&(n, ..)| n > line);
+        let biarc = longest_biarc(&points[i..], tolerance)
+            .filter(|&(n, _)| n > line && arc.is_none_or(|(m, ..)| n > m));
+
+        if let Some((n, [first, second])) = biarc {
+            segments.push(first);
+            segments.push(second);
+            i += n;
+        } else if let Some((n, center, sweep)) = arc {
+            segments.push(Segment::Arc {
+                end: points[i + n],
+                center,
+                sweep,
+            });
+            i += n;
+        } else {
+            segments.push(Segment::Line(points[i + line]));
+            i += line;
+        }
+    }
+    segments
+}
+
+impl Polygon {
+    /// The outline of the polygon as lines and arcs, within `tolerance` of
+    /// its points and of the middles of its edges. Runs of points lying on a
+    /// line or a circle become a single segment, and smooth runs are fitted
+    /// with pairs of arcs meeting at the same tangent (biarcs).
+    pub fn fit_arcs(&self, tolerance: f32) -> Curve {
+        let n = self.points.len();
+        if !self.closed || n < 4 {
+            let start = self.points.first().cloned().unwrap_or(Point(0., 0.));
+            let mut points = self.points.clone();
+            if self.closed {
+                points.push(start);
+            }
+            return Curve {
+                start,
+                segments: segments(&points, tolerance),
+                closed: self.closed,
+            };
+        }
+
+        // A whole circle
+        let mut loop_points = self.points.clone();
+        loop_points.push(self.points[0]);
+        if let Some(center) = circumcenter(self.points[0], self.points[n / 3], self.points[2 * n / 3]) {
+            if let Some(sweep) = sweep(&loop_points, center, tolerance) {
+                return Curve {
+                    start: self.points[0],
+                    segments: vec![Segment::Arc {
+                        end: self.points[0],
+                        center,
+                        sweep,
+                    }],
+                    closed: true,
+                };
+            }
+        }
+
+        // Starts at the sharpest corner, unlikely to be within an arc
+        let turn = |i: usize| {
+            let (a, b, c) = (self.points[(i + n - 1) % n], self.points[i], self.points[(i + 1) % n]);
+            angle(b, Point(2. * b.0 - a.0, 2. * b.1 - a.1), c).abs()
+        };
+        let first = (0..n)
+            .max_by(|&a, &b| turn(a).total_cmp(&turn(b)))
+            .unwrap();
+        let mut curve = fit_closed(&self.points, first, tolerance);
+
+        // Without a sharp corner, the start may fall within an arc, its ends
+        // being left as short lines: the outline is then fitted again from
+        // the start of its longest line
+        let mut current = curve.start;
+        let mut longest: Option<(Point, f32)> = None;
+        for segment in &curve.segments {
+            if let Segment::Line(end) = *segment {
+                if longest.is_none_or(|(_, l)| distance(current, end) > l) {
+                    longest = Some((current, distance(current, end)));
+                }
+            }
+            current = segment.end();
+        }
+        if let Some((start, _)) = longest.filter(|&(start, _)| start != curve.start) {
+            if let Some(first) = self.points.iter().position(|&p| p == start) {
+                curve = fit_closed(&self.points, first, tolerance);
+            }
+        }
+        curve
+    }
+}
+
+/// Lines and arcs around the closed outline through the points, starting
+/// at the point of index `first`
+fn fit_closed(points: &[Point], first: usize, tolerance: f32) -> Curve {
+    let n = points.len();
+    let points: Vec<Point> = (0..(n + 1)).map(|i| points[(first + i) % n]).collect();
+    let mut start = points[0];
+    let mut segments = segments(&points, tolerance);
+
+    // Joins the arcs met at the start, when the outline has no corner
+    let m = segments.len();
+    if m > 2 {
+        if let (Segment::Arc { end, center, sweep }, Segment::Arc { center: c, sweep: s, .. }) =
+            (segments[0], segments[m - 1])
+        {
+            if distance(center, c) <= tolerance && sweep.signum() == s.signum() {
+                start = segments[m - 2].end();
+                segments[0] = Segment::Arc {
+                    end,
+                    center,
+                    sweep: sweep + s,
+                };
+                segments.pop();
+            }
+        }
+    }
+
+    Curve {
+        start,
+        segments,
+        closed: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(center: Point, radius: f32, from: f32, to: f32, steps: usize) -> Vec<Point> {
+        (0..(steps + 1))
+            .map(|i| {
+                let a = from + (to - from) * i as f32 / steps as f32;
+                Point(center.0 + radius * a.cos(), center.1 + radius * a.sin())
+            })
+            .collect()
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_square() {
+        let square = Polygon {
+            closed: true,
+            points: vec![Point(0., 0.), Point(10., 0.), Point(10., 10.), Point(0., 10.)],
+        };
+        let curve = square.fit_arcs(0.01);
+        assert_eq!(curve.segments.len(), 4);
+        assert!(curve.segments.iter().all(|s| s.bulge() == 0.));
+        assert_eq!(curve.segments[3].end(), curve.start);
+    }
+
+    #[test]
+    fn test_circle() {
+        let mut points = circle(Point(5., 5.), 2., 0., 2. * PI, 64);
+        points.pop();
+        let curve = Polygon { closed: true, points }.fit_arcs(0.01);
+        assert_eq!(curve.segments.len(), 1);
+        assert!(curve.segments[0].is_circle());
+        match curve.segments[0] {
+            Segment::Arc { center, sweep, .. } => {
+                assert_close(center.0, 5.);
+                assert_close(center.1, 5.);
+                assert!(sweep > 0.);
+            }
+            _ => panic!("not an arc"),
+        }
+        assert_close(curve.signed_area(), 4. * PI);
+
+        let quarter = curve.slice(PI / 2., PI);
+        assert_close(quarter.length(), PI / 2.);
+        assert_close(distance(quarter.start, Point(5., 5.)), 2.);
+        assert_close(distance(quarter.end(), Point(5., 5.)), 2.);
+    }
+
+    #[test]
+    fn test_rounded() {
+        // A slot: two half circles joined by lines, drawn clockwise
+        let mut points = circle(Point(0., 0.), 1., 1.5 * PI, 0.5 * PI, 16);
+        points.extend(circle(Point(10., 0.), 1., 0.5 * PI, -0.5 * PI, 16));
+        let curve = Polygon { closed: true, points }.fit_arcs(0.01);
+
+        let arcs: Vec<_> = curve.segments.iter().filter(|s| s.bulge() != 0.).collect();
+        assert_eq!(curve.segments.len(), 4);
+        assert_eq!(arcs.len(), 2);
+        // Half circles turning clockwise
+        assert!(arcs.iter().all(|s| (s.bulge() + 1.).abs() < 1e-3));
+        assert_eq!(curve.segments[3].end(), curve.start);
+
+        assert_close(curve.length(), 20. + 2. * PI);
+        assert_close(curve.signed_area(), -(20. + PI));
+        let half = curve.slice(0., curve.length() / 2.);
+        assert!(!half.closed);
+        assert_close(half.start.0, curve.start.0);
+        assert_close(half.length(), 10. + PI);
+        let reverse = curve.reverse();
+        assert_eq!(reverse.start, curve.end());
+        assert_close(reverse.length(), curve.length());
+        assert!(reverse.segments.iter().filter(|s| s.bulge() != 0.).all(|s| (s.bulge() - 1.).abs() < 1e-3));
+
+        let open = Polygon {
+            closed: false,
+            points: circle(Point(0., 0.), 5., 0., PI / 2., 32),
+        };
+        let curve = open.fit_arcs(0.01);
+        assert_eq!(curve.segments.len(), 1);
+        assert!(!curve.closed);
+    }
+
+    /// Distance from `p` to the nearest segment of the curve
+    fn curve_distance(curve: &Curve, p: Point) -> f32 {
+        let mut start = curve.start;
+        let mut nearest = ::std::f32::INFINITY;
+        for segment in &curve.segments {
+            nearest = nearest.min(segment_distance(start, segment, p));
+            start = segment.end();
+        }
+        nearest
+    }
+
+    #[test]
+    fn test_lines() {
+        // Collinear points along the edges of a square
+        let points = (0..40)
+            .map(|i| {
+                let (side, t) = (i / 10, (i % 10) as f32);
+                [Point(t, 0.), Point(10., t), Point(10. - t, 10.), Point(0., 10. - t)][side]
+            })
+            .collect();
+        let curve = Polygon { closed: true, points }.fit_arcs(0.01);
+        assert_eq!(curve.segments.len(), 4);
+        assert!(curve.segments.iter().all(|s| s.bulge() == 0.));
+    }
+
+    #[test]
+    fn test_biarcs() {
+        // A flattened cubic Bézier curve, bending one way then the other
+        let (p0, p1, p2, p3) = (Point(0., 0.), Point(30., 40.), Point(60., -40.), Point(90., 0.));
+        let points: Vec<Point> = (0..201)
+            .map(|i| {
+                let t = i as f32 / 200.;
+                let (a, b, c, d) = ((1. - t).powi(3), 3. * t * (1. - t).powi(2), 3. * t * t * (1. - t), t.powi(3));
+                Point(
+                    a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                    a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+                )
+            })
+            .collect();
+        let polygon = Polygon {
+            closed: false,
+            points: points.clone(),
+        };
+
+        let curve = polygon.fit_arcs(0.05);
+        assert!(curve.segments.len() < 20, "{} segments", curve.segments.len());
+        assert!(curve.segments.iter().filter(|s| s.bulge() != 0.).count() > curve.segments.len() / 2);
+        assert_eq!(curve.start, points[0]);
+        assert_eq!(curve.end(), points[200]);
+        for &p in &points {
+            assert!(curve_distance(&curve, p) <= 0.05 + 1e-4, "{} off the curve", p);
+        }
+
+        // The arcs run on from one another
+        let mut start = curve.start;
+        for pair in curve.segments.windows(2) {
+            let (a, b) = (pair[0].direction(start, 1.), pair[1].direction(pair[0].end(), 0.));
+            assert!(a.0 * b.0 + a.1 * b.1 > 0.9);
+            start = pair[0].end();
+        }
+    }
+}
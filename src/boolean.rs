@@ -0,0 +1,255 @@
+use std::collections::{HashMap, HashSet};
+use path::{Point, Polygon};
+use repair::crossing;
+
+/// Distance along an edge under which another point is taken as lying on it
+const EPSILON: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operation {
+    Union,
+    Intersection,
+    /// The subject minus the clip
+    Difference,
+    Xor,
+}
+
+impl Operation {
+    fn apply(self, subject: bool, clip: bool) -> bool {
+        match self {
+            Operation::Union => subject || clip,
+            Operation::Intersection => subject && clip,
+            Operation::Difference => subject && !clip,
+            Operation::Xor => subject != clip,
+        }
+    }
+}
+
+fn key(p: Point) -> (u32, u32) {
+    (p.0.to_bits(), p.1.to_bits())
+}
+
+fn winding(polygons: &[&Polygon], p: Point) -> i32 {
+    polygons.iter().map(|polygon| polygon.winding(p)).sum()
+}
+
+/// Position of `p` along `[a, b]` if it lies on the segment, ends excluded
+fn position(p: Point, a: Point, b: Point) -> Option<f32> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    if length == 0. {
+        return None;
+    }
+
+    let t = ((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length;
+    let distance = a.cross(b, p).abs() / length.sqrt();
+    if t > EPSILON && t < 1. - EPSILON && distance <= EPSILON * length.sqrt() {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Cuts the edges of the polygons wherever they cross or touch each other
+fn split_edges(edges: &[(Point, Point)]) -> Vec<(Point, Point)> {
+    let mut splits: Vec<Vec<(f32, Point)>> = vec![Vec::new(); edges.len()];
+    for i in 0..edges.len() {
+        let (a1, a2) = edges[i];
+        for j in (i + 1)..edges.len() {
+            let (b1, b2) = edges[j];
+            if a1.0.max(a2.0) < b1.0.min(b2.0) || b1.0.max(b2.0) < a1.0.min(a2.0)
+                || a1.1.max(a2.1) < b1.1.min(b2.1) || b1.1.max(b2.1) < a1.1.min(a2.1)
+            {
+                continue;
+            }
+
+            // Ends lying on the other edge, which covers overlapping edges
+            for &(p, (c1, c2), k) in &[(b1, (a1, a2), i), (b2, (a1, a2), i), (a1, (b1, b2), j), (a2, (b1, b2), j)] {
+                if let Some(t) = position(p, c1, c2) {
+                    splits[k].push((t, p));
+                }
+            }
+
+            if let Some((t, u)) = crossing(a1, a2, b1, b2) {
+                if t > EPSILON && t < 1. - EPSILON && u > EPSILON && u < 1. - EPSILON {
+                    let p = Point(a1.0 + t * (a2.0 - a1.0), a1.1 + t * (a2.1 - a1.1));
+                    splits[i].push((t, p));
+                    splits[j].push((u, p));
+                }
+            }
+        }
+    }
+
+    let mut pieces = Vec::new();
+    for (&(a, b), splits) in edges.iter().zip(splits.iter_mut()) {
+        splits.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut start = a;
+        for &(_, p) in splits.iter().chain(Some(&(1., b))) {
+            if start != p {
+                pieces.push((start, p));
+            }
+            start = p;
+        }
+    }
+    pieces
+}
+
+/// Chains edges into closed polygons, taking the sharpest right turn where
+/// several edges leave the same point
+fn chain(edges: Vec<(Point, Point)>) -> Vec<Polygon> {
+    let mut outgoing: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (i, &(a, _)) in edges.iter().enumerate() {
+        outgoing.entry(key(a)).or_default().push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut polygons = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+
+        let mut points = Vec::new();
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (a, b) = edges[current];
+            points.push(a);
+
+            let heading = (b.1 - a.1).atan2(b.0 - a.0);
+            let next = outgoing.get(&key(b)).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .cloned()
+                    .filter(|&i| !used[i] || i == first)
+                    .min_by(|&i, &j| {
+                        let turn = |i: usize| {
+                            let (c, d) = edges[i];
+                            let angle = (d.1 - c.1).atan2(d.0 - c.0) - heading;
+                            angle.sin().atan2(angle.cos())
+                        };
+                        turn(i).total_cmp(&turn(j))
+                    })
+            });
+
+            match next {
+                Some(next) if next != first => current = next,
+                _ => break,
+            }
+        }
+
+        if points.len() >= 3 {
+            polygons.push(Polygon { closed: true, points });
+        }
+    }
+    polygons
+}
+
+/// Combines two sets of closed polygons, whose filled regions are given by
+/// their non-zero winding number: holes turn against their outer boundary, as
+/// in a `Part`. The result is made of simple polygons turning
+/// counter-clockwise around the filled regions and clockwise around holes.
+pub fn boolean(subject: &[Polygon], clip: &[Polygon], operation: Operation) -> Vec<Polygon> {
+    let closed = |polygons: &[Polygon]| -> Vec<usize> {
+        (0..polygons.len())
+            .filter(|&i| polygons[i].closed && polygons[i].points.len() >= 3)
+            .collect()
+    };
+    let subject: Vec<&Polygon> = closed(subject).into_iter().map(|i| &subject[i]).collect();
+    let clip: Vec<&Polygon> = closed(clip).into_iter().map(|i| &clip[i]).collect();
+
+    let edges: Vec<_> = subject.iter().chain(&clip).flat_map(|polygon| polygon.edges()).collect();
+    let filled = |p: Point| operation.apply(winding(&subject, p) != 0, winding(&clip, p) != 0);
+
+    // Keep the edges between a filled and an unfilled region, once each
+    let mut seen = HashSet::new();
+    let mut boundary = Vec::new();
+    for (a, b) in split_edges(&edges) {
+        let (ka, kb) = (key(a), key(b));
+        if !seen.insert(if ka < kb { (ka, kb) } else { (kb, ka) }) {
+            continue;
+        }
+
+        let middle = Point((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+        let normal = Point(-(b.1 - a.1) * 1e-3, (b.0 - a.0) * 1e-3);
+        let left = filled(Point(middle.0 + normal.0, middle.1 + normal.1));
+        let right = filled(Point(middle.0 - normal.0, middle.1 - normal.1));
+        match (left, right) {
+            (true, false) => boundary.push((a, b)),
+            (false, true) => boundary.push((b, a)),
+            _ => {}
+        }
+    }
+
+    chain(boundary)
+}
+
+pub fn union(subject: &[Polygon], clip: &[Polygon]) -> Vec<Polygon> {
+    boolean(subject, clip, Operation::Union)
+}
+
+pub fn intersection(subject: &[Polygon], clip: &[Polygon]) -> Vec<Polygon> {
+    boolean(subject, clip, Operation::Intersection)
+}
+
+pub fn difference(subject: &[Polygon], clip: &[Polygon]) -> Vec<Polygon> {
+    boolean(subject, clip, Operation::Difference)
+}
+
+pub fn xor(subject: &[Polygon], clip: &[Polygon]) -> Vec<Polygon> {
+    boolean(subject, clip, Operation::Xor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Polygon {
+        Polygon {
+            closed: true,
+            points: vec![Point(x, y), Point(x + w, y), Point(x + w, y + h), Point(x, y + h)],
+        }
+    }
+
+    fn area(polygons: &[Polygon]) -> f32 {
+        polygons.iter().map(|p| p.signed_area()).sum()
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let (a, b) = (vec![rect(0., 0., 2., 2.)], vec![rect(1., 1., 2., 2.)]);
+
+        let merged = union(&a, &b);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].points.len(), 8);
+        assert_eq!(area(&merged), 7.);
+
+        let common = intersection(&a, &b);
+        assert_eq!(common.len(), 1);
+        assert_eq!(area(&common), 1.);
+
+        assert_eq!(area(&difference(&a, &b)), 3.);
+        assert_eq!(xor(&a, &b).len(), 2);
+        assert_eq!(area(&xor(&a, &b)), 6.);
+    }
+
+    #[test]
+    fn test_hole() {
+        let frame = difference(&[rect(0., 0., 4., 4.)], &[rect(1., 1., 2., 2.)]);
+        assert_eq!(frame.len(), 2);
+        assert_eq!(area(&frame), 12.);
+        assert!(frame.iter().any(|p| p.signed_area() == -4.));
+
+        // Clipping the frame keeps its hole
+        let clipped = intersection(&frame, &[rect(0., 0., 4., 2.)]);
+        assert_eq!(area(&clipped), 6.);
+    }
+
+    #[test]
+    fn test_shared_edge() {
+        let merged = union(&[rect(0., 0., 1., 1.), rect(1., 0., 1., 1.)], &[]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(area(&merged), 2.);
+        assert!(difference(&[rect(0., 0., 1., 1.)], &[rect(0., 0., 1., 1.)]).is_empty());
+    }
+}
@@ -0,0 +1,409 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use part::{Contour, Part};
+use path::{segments_intersect, Point, Polygon};
+use repair::Warning;
+use svg::PolygonGroup;
+
+/// Distance under which points are considered equal, and vertices collinear
+const EPSILON: f32 = 1e-3;
+
+fn distance(a: Point, b: Point) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+/// Distance from `p` to the segment `[a, b]`
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+    if length == 0. {
+        return distance(p, a);
+    }
+
+    let t = (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0., 1.);
+    distance(p, Point(a.0 + t * dx, a.1 + t * dy))
+}
+
+/// A vertex which may be removed, the cheapest first
+struct Candidate {
+    cost: f32,
+    vertex: usize,
+    version: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+/// Vertices of a polygon being simplified, as a doubly linked list over the
+/// original points
+struct Reducer<'a> {
+    points: &'a [Point],
+    closed: bool,
+    /// Whether only the vertices whose removal grows the left side of the
+    /// outline may be removed
+    outward: bool,
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    removed: Vec<bool>,
+    versions: Vec<usize>,
+    len: usize,
+}
+
+impl<'a> Reducer<'a> {
+    fn new(points: &'a [Point], closed: bool, outward: bool) -> Self {
+        let n = points.len();
+        Reducer {
+            points,
+            closed,
+            outward,
+            prev: (0..n).map(|i| (i + n - 1) % n).collect(),
+            next: (0..n).map(|i| (i + 1) % n).collect(),
+            removed: vec![false; n],
+            versions: vec![0; n],
+            len: n,
+        }
+    }
+
+    /// Largest distance between the segment replacing the vertex and the
+    /// original points it stands for, if the vertex may be removed
+    fn cost(&self, vertex: usize) -> Option<f32> {
+        if !self.closed && (vertex == 0 || vertex == self.points.len() - 1) {
+            return None;
+        }
+
+        let (a, c) = (self.prev[vertex], self.next[vertex]);
+        let (pa, pc) = (self.points[a], self.points[c]);
+        if self.outward && pa.cross(self.points[vertex], pc) > 0. {
+            return None;
+        }
+
+        let mut cost = 0f32;
+        let mut i = (a + 1) % self.points.len();
+        while i != c {
+            cost = cost.max(segment_distance(self.points[i], pa, pc));
+            i = (i + 1) % self.points.len();
+        }
+        Some(cost)
+    }
+
+    /// Whether joining the neighbours of the vertex would cross another edge
+    fn crosses(&self, vertex: usize) -> bool {
+        let (a, c) = (self.prev[vertex], self.next[vertex]);
+        let (pa, pc) = (self.points[a], self.points[c]);
+        (0..self.points.len())
+            .filter(|&i| !self.removed[i] && i != vertex && i != a && i != c)
+            .filter(|&i| self.closed || i != self.points.len() - 1)
+            .any(|i| {
+                let j = self.next[i];
+                j != vertex && j != a && j != c
+                    && segments_intersect(pa, pc, self.points[i], self.points[j])
+            })
+    }
+
+    fn reduce(mut self, tolerance: f32) -> Vec<Point> {
+        let min = if self.closed { 3 } else { 2 };
+        let mut heap = BinaryHeap::new();
+        for vertex in 0..self.points.len() {
+            if let Some(cost) = self.cost(vertex) {
+                heap.push(Candidate { cost, vertex, version: 0 });
+            }
+        }
+
+        while let Some(Candidate { cost, vertex, version }) = heap.pop() {
+            if self.len <= min || cost > tolerance {
+                break;
+            }
+            if self.removed[vertex] || version != self.versions[vertex] || self.crosses(vertex) {
+                continue;
+            }
+
+            let (a, c) = (self.prev[vertex], self.next[vertex]);
+            self.next[a] = c;
+            self.prev[c] = a;
+            self.removed[vertex] = true;
+            self.len -= 1;
+
+            for &neighbour in &[a, c] {
+                self.versions[neighbour] += 1;
+                if let Some(cost) = self.cost(neighbour) {
+                    heap.push(Candidate {
+                        cost,
+                        vertex: neighbour,
+                        version: self.versions[neighbour],
+                    });
+                }
+            }
+        }
+
+        self.points
+            .iter()
+            .zip(&self.removed)
+            .filter(|&(_, &removed)| !removed)
+            .map(|(&point, _)| point)
+            .collect()
+    }
+}
+
+impl Polygon {
+    /// Merges consecutive points closer than `tolerance`, including the last
+    /// and first points of a closed polygon
+    pub fn dedup(&mut self, tolerance: f32) {
+        let mut points: Vec<Point> = Vec::with_capacity(self.points.len());
+        for &point in &self.points {
+            if points.last().is_none_or(|&last| distance(last, point) > tolerance) {
+                points.push(point);
+            }
+        }
+
+        if self.closed {
+            while points.len() > 1 && distance(points[0], points[points.len() - 1]) <= tolerance {
+                points.pop();
+            }
+        }
+
+        self.points = points;
+    }
+
+    /// Removes the vertices lying within `tolerance` of the outline once they
+    /// are gone, on either side of it
+    pub fn remove_collinear(&mut self, tolerance: f32) {
+        self.points = Reducer::new(&self.points, self.closed, false).reduce(tolerance);
+    }
+
+    /// Removes vertices one at a time, the one whose replacing segment lies
+    /// closest to the original points it stands for first, as long as the
+    /// outline stays within `tolerance` of the original.
+    ///
+    /// Closed polygons only ever grow on their left side, which holds the
+    /// material of a `Part`: outward for counter-clockwise outlines, into the
+    /// hole for clockwise ones.
+    pub fn simplify(&mut self, tolerance: f32) {
+        self.points = Reducer::new(&self.points, self.closed, self.closed).reduce(tolerance);
+    }
+
+    /// Drops duplicate and collinear points, then simplifies the polygon
+    pub fn cleanup(&mut self, tolerance: f32) {
+        self.dedup(EPSILON);
+        self.remove_collinear(EPSILON);
+        self.simplify(tolerance);
+    }
+}
+
+impl Contour {
+    fn cleanup(&mut self, tolerance: f32) {
+        self.outer.cleanup(tolerance);
+        for hole in &mut self.holes {
+            hole.polygon.cleanup(tolerance);
+            for island in &mut hole.islands {
+                island.cleanup(tolerance);
+            }
+        }
+    }
+}
+
+impl Part {
+    /// Cleans up every polygon of the part, see `Polygon::cleanup`
+    pub fn cleanup(&mut self, tolerance: f32) {
+        for contour in &mut self.contours {
+            contour.cleanup(tolerance);
+        }
+        for polygon in &mut self.open {
+            polygon.cleanup(tolerance);
+        }
+    }
+}
+
+/// Finds the open polygon with an end closest to `p`, within `tolerance`,
+/// and whether it must be reversed to start there
+fn nearest_end(polygons: &[Polygon], p: Point, tolerance: f32) -> Option<(usize, bool)> {
+    polygons
+        .iter()
+        .enumerate()
+        .flat_map(|(i, polygon)| {
+            let first = (i, false, distance(p, polygon.points[0]));
+            let last = (i, true, distance(p, polygon.points[polygon.points.len() - 1]));
+            vec![first, last]
+        })
+        .filter(|&(_, _, d)| d <= tolerance)
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(i, reversed, _)| (i, reversed))
+}
+
+/// Closes the open polygons ending within `tolerance` of their start, and
+/// chains the ones sharing endpoints into closed loops. The polygons which
+/// cannot be closed are returned open.
+pub fn close_contours(polygons: Vec<Polygon>, tolerance: f32) -> Vec<Polygon> {
+    let (mut closed, mut chains): (Vec<_>, Vec<_>) = polygons
+        .into_iter()
+        .partition(|polygon| polygon.closed || polygon.points.is_empty());
+    let mut open = Vec::new();
+
+    while let Some(mut chain) = chains.pop() {
+        let mut flipped = false;
+        loop {
+            let (first, last) = (chain.points[0], chain.points[chain.points.len() - 1]);
+            if chain.points.len() > 3 && distance(first, last) <= tolerance {
+                chain.points.pop();
+                chain.closed = true;
+                closed.push(chain);
+                break;
+            }
+
+            match nearest_end(&chains, last, tolerance) {
+                Some((i, reversed)) => {
+                    let mut next = chains.swap_remove(i);
+                    if reversed {
+                        next.reverse();
+                    }
+                    chain.points.extend(next.points.into_iter().skip(1));
+                }
+                // Nothing will ever join the start again once it is checked
+                None if !flipped => {
+                    chain.reverse();
+                    flipped = true;
+                }
+                None => {
+                    open.push(chain);
+                    break;
+                }
+            }
+        }
+    }
+
+    closed.extend(open);
+    closed
+}
+
+impl PolygonGroup {
+    /// Closes the contours of the group, see `close_contours`, warning about
+    /// each one left open
+    pub fn close_contours(&mut self, tolerance: f32) -> Vec<Warning> {
+        let polygons = ::std::mem::take(&mut self.polygons);
+        self.polygons = close_contours(polygons, tolerance);
+        self.polygons
+            .iter()
+            .filter(|polygon| !polygon.closed && !polygon.points.is_empty())
+            .map(|polygon| {
+                let (first, last) = (polygon.points[0], polygon.points[polygon.points.len() - 1]);
+                Warning {
+                    source: self.source.clone(),
+                    message: format!(
+                        "open contour of {} points, its ends {} apart",
+                        polygon.points.len(),
+                        distance(first, last)
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use part::FillRule;
+
+    fn polygon(closed: bool, points: &[(f32, f32)]) -> Polygon {
+        Polygon {
+            closed,
+            points: points.iter().map(|&(x, y)| Point(x, y)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut square = polygon(true, &[(0., 0.), (0., 0.), (1., 0.), (1., 1.), (1., 1.0001), (0., 1.), (0., 0.)]);
+        square.dedup(1e-3);
+        assert_eq!(square.points, polygon(true, &[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]).points);
+    }
+
+    #[test]
+    fn test_collinear() {
+        let mut square = polygon(true, &[(0., 0.), (0.5, 0.), (1., 0.), (1., 0.5), (1., 1.), (0., 1.)]);
+        square.remove_collinear(1e-3);
+        assert_eq!(square.points, polygon(true, &[(0., 0.), (1., 0.), (1., 1.), (0., 1.)]).points);
+
+        let mut line = polygon(false, &[(0., 0.), (1., 0.), (2., 0.)]);
+        line.remove_collinear(1e-3);
+        assert_eq!(line.points, polygon(false, &[(0., 0.), (2., 0.)]).points);
+    }
+
+    #[test]
+    fn test_simplify() {
+        // A square with a dent and a bump on its bottom side
+        let points = [(0., 0.), (1., 0.), (1.5, 0.1), (2., 0.), (3., 0.), (3.5, -0.1), (4., 0.), (4., 4.), (0., 4.)];
+        let mut outer = polygon(true, &points);
+        outer.simplify(0.2);
+        assert_eq!(outer.points.len(), 5);
+        assert!(outer.points.contains(&Point(3.5, -0.1)));
+        assert!(!outer.points.contains(&Point(1.5, 0.1)));
+        assert!(outer.signed_area() >= polygon(true, &points).signed_area());
+
+        // The same outline as a hole may only shrink
+        let mut hole = polygon(true, &points);
+        hole.reverse();
+        hole.simplify(0.2);
+        assert!(hole.points.contains(&Point(1.5, 0.1)));
+        assert!(!hole.points.contains(&Point(3.5, -0.1)));
+        assert!(hole.signed_area() >= -polygon(true, &points).signed_area());
+
+        let mut outer = polygon(true, &points);
+        outer.simplify(0.01);
+        assert_eq!(outer.points.len(), points.len());
+    }
+
+    #[test]
+    fn test_close_contours() {
+        let polygons = vec![
+            polygon(false, &[(0., 0.), (1., 0.), (1., 1.), (0.0005, 0.)]),
+            polygon(false, &[(5., 0.), (6., 0.)]),
+            polygon(false, &[(5., 1.), (6., 0.)]),
+            polygon(false, &[(5., 0.), (5., 1.0005)]),
+            polygon(false, &[(8., 0.), (9., 0.), (9., 1.)]),
+            polygon(true, &[(0., 5.), (1., 5.), (1., 6.)]),
+        ];
+
+        let polygons = close_contours(polygons, 1e-3);
+        assert_eq!(polygons.len(), 4);
+        assert_eq!(polygons.iter().filter(|polygon| polygon.closed).count(), 3);
+        assert_eq!(polygons[3].points.len(), 3);
+        assert!(!polygons[3].closed);
+        for polygon in &polygons[0..3] {
+            assert_eq!(polygon.points.len(), 3);
+            assert!((polygon.signed_area().abs() - 0.5).abs() < 1e-3);
+        }
+
+        let mut group = PolygonGroup {
+            id: None,
+            label: None,
+            source: String::from("/svg/path[1]"),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::NonZero,
+            polygons: vec![
+                polygon(false, &[(0., 0.), (1., 0.), (1., 1.), (0.0005, 0.)]),
+                polygon(false, &[(8., 0.), (9., 0.), (9., 1.)]),
+            ],
+        };
+        let warnings = group.close_contours(1e-3);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "/svg/path[1]: open contour of 3 points, its ends 1.4142135 apart");
+    }
+}
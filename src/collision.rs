@@ -0,0 +1,174 @@
+use index::Grid;
+use part::{Contour, Part};
+use path::{Bounding, Point, Polygon};
+
+/// Whether the segments `[a1, a2]` and `[b1, b2]` cross each other, merely
+/// touching or overlapping segments aside
+fn segments_cross(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let d1 = b1.cross(b2, a1);
+    let d2 = b1.cross(b2, a2);
+    let d3 = a1.cross(a2, b1);
+    let d4 = a1.cross(a2, b2);
+    ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.)) && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.))
+}
+
+/// Whether an edge of the first polygons crosses an edge of the second ones
+fn outlines_cross(a: &[&Polygon], b: &[&Polygon]) -> bool {
+    a.iter().any(|a| {
+        let bounds = a.bounding();
+        b.iter()
+            .filter(|b| bounds.intersects(&b.bounding(), true))
+            .any(|b| a.edges().any(|(a1, a2)| b.edges().any(|(b1, b2)| segments_cross(a1, a2, b1, b2))))
+    })
+}
+
+impl Contour {
+    /// The outer boundaries of the contour and its islands
+    fn outers<'a>(&'a self, outers: &mut Vec<&'a Polygon>) {
+        outers.push(&self.outer);
+        for hole in &self.holes {
+            for island in &hole.islands {
+                island.outers(outers);
+            }
+        }
+    }
+}
+
+impl Part {
+    fn closed(&self) -> Vec<&Polygon> {
+        self.polygons().into_iter().map(|(polygon, _)| polygon).collect()
+    }
+
+    /// A point inside the material of each outer boundary
+    fn samples(&self) -> Vec<Point> {
+        let mut outers = Vec::new();
+        for contour in &self.contours {
+            contour.outers(&mut outers);
+        }
+        outers.iter().filter_map(|outer| outer.sides()).map(|(left, _)| left).collect()
+    }
+
+    /// Whether the point lies in the material of the part
+    pub fn contains_point(&self, p: Point) -> bool {
+        // Outer boundaries and holes alternate from the outside in
+        let count = self.polygons()
+            .iter()
+            .filter(|&&(polygon, _)| polygon.contains(p))
+            .count();
+        count % 2 == 1
+    }
+
+    /// Whether the materials of the two parts overlap, parts merely touching
+    /// each other or lying in a hole of the other one aside
+    pub fn overlaps(&self, other: &Part) -> bool {
+        if !self.bounding().intersects(&other.bounding(), true) {
+            return false;
+        }
+
+        outlines_cross(&self.closed(), &other.closed())
+            || other.samples().iter().any(|&p| self.contains_point(p))
+            || self.samples().iter().any(|&p| other.contains_point(p))
+    }
+
+    /// Whether the material of the other part lies entirely in the material
+    /// of this one
+    pub fn contains(&self, other: &Part) -> bool {
+        if other.contours.is_empty() || !self.bounding().contains(&other.bounding()) {
+            return false;
+        }
+
+        // No hole of this part may lie in the other one
+        !outlines_cross(&self.closed(), &other.closed())
+            && other.samples().iter().all(|&p| self.contains_point(p))
+            && !self.holes().iter().any(|hole| hole.sides().is_some_and(|(_, right)| other.contains_point(right)))
+    }
+}
+
+/// The pairs of parts, already in place, whose materials overlap
+pub fn collisions(parts: &[Part]) -> Vec<(usize, usize)> {
+    let bounds: Vec<_> = parts.iter().map(Bounding::bounding).collect();
+    let cell = bounds.iter().map(|b| b.width().max(b.height())).sum::<f32>() / parts.len().max(1) as f32;
+
+    let mut grid: Grid<usize> = Grid::new(cell);
+    let mut collisions = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        if part.contours.is_empty() {
+            continue;
+        }
+        for (_, _, &j) in grid.query(&bounds[i]) {
+            if parts[j].overlaps(part) {
+                collisions.push((j, i));
+            }
+        }
+        grid.insert(bounds[i].clone(), i);
+    }
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use part::FillRule;
+    use svg::PolygonGroup;
+
+    fn square(x: f32, y: f32, size: f32) -> Polygon {
+        Polygon {
+            closed: true,
+            points: vec![
+                Point(x, y),
+                Point(x + size, y),
+                Point(x + size, y + size),
+                Point(x, y + size),
+            ],
+        }
+    }
+
+    fn part(polygons: Vec<Polygon>) -> Part {
+        Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons,
+        })
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let a = part(vec![square(0., 0., 2.)]);
+        assert!(a.overlaps(&part(vec![square(1., 1., 2.)])));
+        assert!(a.overlaps(&a));
+        assert!(!a.overlaps(&part(vec![square(2., 0., 2.)])));
+        assert!(!a.overlaps(&part(vec![square(5., 0., 2.)])));
+        assert!(a.overlaps(&part(vec![square(0.5, 0.5, 1.)])));
+    }
+
+    #[test]
+    fn test_holes() {
+        let frame = part(vec![square(0., 0., 10.), square(2., 2., 6.)]);
+        let inside = part(vec![square(3., 3., 2.)]);
+        assert!(!frame.overlaps(&inside));
+        assert!(!inside.overlaps(&frame));
+        assert!(frame.overlaps(&part(vec![square(1., 1., 2.)])));
+
+        assert!(frame.contains(&part(vec![square(0.5, 0.5, 1.)])));
+        assert!(!frame.contains(&inside));
+        assert!(!part(vec![square(0., 0., 10.)]).contains(&part(vec![square(9., 9., 2.)])));
+        // The hole of the frame lies inside the square
+        assert!(!frame.contains(&part(vec![square(1., 1., 8.)])));
+        assert!(frame.contains_point(Point(1., 1.)));
+        assert!(!frame.contains_point(Point(5., 5.)));
+    }
+
+    #[test]
+    fn test_collisions() {
+        let parts: Vec<_> = (0..20).map(|i| part(vec![square(i as f32 * 3., 0., 2.)])).collect();
+        assert!(collisions(&parts).is_empty());
+
+        let mut parts = parts;
+        parts.push(part(vec![square(4., 1., 4.)]));
+        assert_eq!(collisions(&parts), vec![(1, 20), (2, 20)]);
+    }
+}
@@ -0,0 +1,813 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use cleanup::close_contours;
+use index::Grid;
+use part::FillRule;
+use path::{BoundingBox, Point, Polygon};
+use svg::{Meta, Node, ParseOptions, Root};
+use transform::Transform;
+use SNAPPING;
+
+/// Largest distance between a curve and the segments drawing it, in
+/// millimetres
+const CURVE_TOLERANCE: f32 = 0.01;
+
+/// Segments drawing each knot span of a spline
+const SPLINE_STEPS: usize = 16;
+
+/// A group code and its value
+type Pair<'a> = (i32, &'a str);
+
+/// Reads the group code and value pairs of an ASCII DXF file
+fn pairs<'a>(input: &'a str) -> Option<Vec<Pair<'a>>> {
+    let mut lines = input.lines();
+    let mut pairs = Vec::new();
+    while let Some(code) = lines.next() {
+        if code.trim().is_empty() {
+            continue;
+        }
+        let code = code.trim().parse().ok()?;
+        let value = lines.next()?.trim();
+        pairs.push((code, value));
+        if (code, value) == (0, "EOF") {
+            break;
+        }
+    }
+    Some(pairs)
+}
+
+/// A record of the file, from a `0` group code to the next one
+struct Entity<'a> {
+    kind: &'a str,
+    codes: &'a [Pair<'a>],
+    /// The `VERTEX` records following a `POLYLINE`
+    vertices: Vec<Entity<'a>>,
+}
+
+impl<'a> Entity<'a> {
+    fn get(&self, code: i32) -> Option<&'a str> {
+        self.codes.iter().find(|&&(c, _)| c == code).map(|&(_, v)| v)
+    }
+
+    fn float(&self, code: i32) -> Option<f32> {
+        self.get(code)?.parse().ok()
+    }
+
+    fn int(&self, code: i32) -> Option<i32> {
+        self.get(code)?.parse().ok()
+    }
+
+    /// The point whose X is given by `code`, and Y by `code + 10`
+    fn point(&self, code: i32) -> Point {
+        Point(self.float(code).unwrap_or(0.), self.float(code + 10).unwrap_or(0.))
+    }
+
+    /// Every point whose X is given by `code`, in order
+    fn points(&self, code: i32) -> Vec<Point> {
+        let mut points: Vec<Point> = Vec::new();
+        for &(c, v) in self.codes {
+            let v = v.parse().unwrap_or(0.);
+            if c == code {
+                points.push(Point(v, 0.));
+            } else if c == code + 10 {
+                if let Some(p) = points.last_mut() {
+                    p.1 = v;
+                }
+            }
+        }
+        points
+    }
+
+    fn floats(&self, code: i32) -> Vec<f32> {
+        self.codes
+            .iter()
+            .filter(|&&(c, _)| c == code)
+            .filter_map(|&(_, v)| v.parse().ok())
+            .collect()
+    }
+
+    fn layer(&self) -> &'a str {
+        self.get(8).unwrap_or("0")
+    }
+
+    fn is_closed(&self) -> bool {
+        self.int(70).unwrap_or(0) & 1 != 0
+    }
+
+    /// Entities drawn in their object coordinate system are mirrored when
+    /// their extrusion direction points down
+    fn ocs(&self) -> Transform {
+        match self.kind {
+            "ARC" | "CIRCLE" | "LWPOLYLINE" | "POLYLINE" | "INSERT" if self.float(230).unwrap_or(1.) < 0. => {
+                Transform::scale(-1., 1.)
+            }
+            _ => Transform::identity(),
+        }
+    }
+}
+
+/// Splits pairs in records, starting with a `0` group code
+fn records<'a>(pairs: &'a [Pair<'a>]) -> Vec<Entity<'a>> {
+    let mut records = Vec::new();
+    let mut start = 0;
+    for i in 1..(pairs.len() + 1) {
+        if i == pairs.len() || pairs[i].0 == 0 {
+            if pairs[start].0 == 0 {
+                records.push(Entity {
+                    kind: pairs[start].1,
+                    codes: &pairs[(start + 1)..i],
+                    vertices: Vec::new(),
+                });
+            }
+            start = i;
+        }
+    }
+    records
+}
+
+/// Attaches the vertices of the polylines to them, dropping the attributes
+/// of the block references
+fn entities<'a>(records: Vec<Entity<'a>>) -> Vec<Entity<'a>> {
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut polyline = false;
+    for record in records {
+        match record.kind {
+            "VERTEX" if polyline => entities.last_mut().unwrap().vertices.push(record),
+            "SEQEND" => polyline = false,
+            "VERTEX" | "ATTRIB" => {}
+            _ => {
+                polyline = record.kind == "POLYLINE";
+                entities.push(record);
+            }
+        }
+    }
+    entities
+}
+
+/// Block definition: base point and entities
+struct Block<'a> {
+    base: Point,
+    entities: Vec<Entity<'a>>,
+}
+
+struct Drawing<'a> {
+    /// Layers in the order of the table, and whether they are shown
+    layers: Vec<(&'a str, bool)>,
+    blocks: HashMap<&'a str, Block<'a>>,
+    entities: Vec<Entity<'a>>,
+    /// Length of a drawing unit, in millimetres
+    scale: f32,
+}
+
+impl<'a> Drawing<'a> {
+    fn new(pairs: &'a [Pair<'a>]) -> Option<Self> {
+        let mut sections = HashMap::new();
+        let mut i = 0;
+        while i + 1 < pairs.len() {
+            if pairs[i] == (0, "SECTION") && pairs[i + 1].0 == 2 {
+                let end = pairs[i..].iter().position(|&p| p == (0, "ENDSEC")).map_or(pairs.len(), |n| i + n);
+                sections.insert(pairs[i + 1].1, &pairs[(i + 2)..end]);
+                i = end;
+            }
+            i += 1;
+        }
+
+        let header = sections.get("HEADER").cloned().unwrap_or(&[]);
+        let units = header
+            .iter()
+            .position(|&p| p == (9, "$INSUNITS"))
+            .and_then(|i| header.get(i + 1))
+            .and_then(|&(_, v)| v.parse().ok());
+        let scale = match units {
+            Some(1) => 25.4,
+            Some(2) => 304.8,
+            Some(5) => 10.,
+            Some(6) => 1000.,
+            _ => 1.,
+        };
+
+        let layers = records(sections.get("TABLES").cloned().unwrap_or(&[]))
+            .into_iter()
+            .filter(|record| record.kind == "LAYER")
+            .filter_map(|layer| {
+                let off = layer.int(62).unwrap_or(0) < 0;
+                let frozen = layer.int(70).unwrap_or(0) & 1 != 0;
+                Some((layer.get(2)?, !off && !frozen))
+            })
+            .collect();
+
+        let mut blocks = HashMap::new();
+        let mut block: Option<(&str, Point, Vec<Entity>)> = None;
+        for record in entities(records(sections.get("BLOCKS").cloned().unwrap_or(&[]))) {
+            match record.kind {
+                "BLOCK" => block = Some((record.get(2).unwrap_or(""), record.point(10), Vec::new())),
+                "ENDBLK" => if let Some((name, base, entities)) = block.take() {
+                    blocks.insert(name, Block { base, entities });
+                },
+                _ => if let Some((_, _, ref mut entities)) = block {
+                    entities.push(record);
+                },
+            }
+        }
+
+        Some(Drawing {
+            layers,
+            blocks,
+            entities: entities(records(sections.get("ENTITIES")?)),
+            scale,
+        })
+    }
+
+    fn is_shown(&self, layer: &str) -> bool {
+        self.layers.iter().find(|&&(name, _)| name == layer).is_none_or(|&(_, shown)| shown)
+    }
+}
+
+/// State shared while converting the drawing
+struct Context<'a> {
+    drawing: &'a Drawing<'a>,
+    options: ParseOptions,
+    /// Curve tolerance, in drawing units
+    tolerance: f32,
+    /// Blocks being inserted, used to break reference cycles
+    stack: Vec<&'a str>,
+}
+
+/// The points of an arc, its start aside
+fn arc(center: Point, radius: f32, start: f32, sweep: f32, tolerance: f32) -> Vec<Point> {
+    let steps = if radius > tolerance {
+        let step = 2. * (1. - tolerance / radius).acos();
+        ((sweep.abs() / step).ceil() as usize).clamp(1, 1024)
+    } else {
+        1
+    };
+
+    (1..(steps + 1))
+        .map(|i| {
+            let angle = start + sweep * i as f32 / steps as f32;
+            Point(center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// The points of a polyline segment, its start aside. The bulge is the
+/// tangent of a quarter of the angle of the arc, negative if clockwise
+fn bulge(p1: Point, p2: Point, bulge: f32, tolerance: f32) -> Vec<Point> {
+    if bulge.abs() < 1e-6 || p1 == p2 {
+        return vec![p2];
+    }
+
+    let (dx, dy) = (p2.0 - p1.0, p2.1 - p1.1);
+    let k = (1. - bulge * bulge) / (4. * bulge);
+    let center = Point((p1.0 + p2.0) / 2. - dy * k, (p1.1 + p2.1) / 2. + dx * k);
+    let radius = (p1.0 - center.0).hypot(p1.1 - center.1);
+    let start = (p1.1 - center.1).atan2(p1.0 - center.0);
+    let mut points = arc(center, radius, start, 4. * bulge.atan(), tolerance);
+    // Ends exactly on the next vertex
+    points.pop();
+    points.push(p2);
+    points
+}
+
+/// Builds a polygon, the last point of a closed one being dropped if it
+/// repeats the first
+fn polygon(mut points: Vec<Point>, closed: bool) -> Polygon {
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    Polygon { closed, points }
+}
+
+/// Polygon through vertices with bulges
+fn polyline(vertices: &[(Point, f32)], closed: bool, tolerance: f32) -> Polygon {
+    let mut points = vertices.first().map(|&(p, _)| p).into_iter().collect::<Vec<_>>();
+    let n = vertices.len();
+    for i in 0..(if closed { n } else { n.saturating_sub(1) }) {
+        let ((p1, b), (p2, _)) = (vertices[i], vertices[(i + 1) % n]);
+        points.extend(bulge(p1, p2, b, tolerance));
+    }
+    polygon(points, closed)
+}
+
+fn lwpolyline(entity: &Entity, tolerance: f32) -> Polygon {
+    let mut vertices: Vec<(Point, f32)> = Vec::new();
+    for &(c, v) in entity.codes {
+        let v = v.parse().unwrap_or(0.);
+        match (c, vertices.last_mut()) {
+            (10, _) => vertices.push((Point(v, 0.), 0.)),
+            (20, Some(vertex)) => (vertex.0).1 = v,
+            (42, Some(vertex)) => vertex.1 = v,
+            _ => {}
+        }
+    }
+    polyline(&vertices, entity.is_closed(), tolerance)
+}
+
+fn ellipse(entity: &Entity, tolerance: f32) -> Polygon {
+    let center = entity.point(10);
+    let major = entity.point(11);
+    let ratio = entity.float(40).unwrap_or(1.);
+    let minor = Point(-major.1 * ratio, major.0 * ratio);
+    let start = entity.float(41).unwrap_or(0.);
+    let mut sweep = (entity.float(42).unwrap_or(2. * PI) - start).rem_euclid(2. * PI);
+    if sweep < 1e-6 {
+        sweep = 2. * PI;
+    }
+
+    // Sampled as the circle of the major radius
+    let points = arc(Point(0., 0.), 1., start, sweep, tolerance / major.0.hypot(major.1));
+    let at = |p: Point| {
+        Point(center.0 + major.0 * p.0 + minor.0 * p.1, center.1 + major.1 * p.0 + minor.1 * p.1)
+    };
+    let first = at(Point(start.cos(), start.sin()));
+    let closed = sweep > 2. * PI - 1e-4;
+    let points = if closed {
+        points.into_iter().map(at).collect()
+    } else {
+        Some(first).into_iter().chain(points.into_iter().map(at)).collect()
+    };
+    polygon(points, closed)
+}
+
+/// Point of a rational B-spline, found with the de Boor algorithm
+fn de_boor(t: f32, degree: usize, knots: &[f32], control: &[(Point, f32)]) -> Point {
+    let n = control.len();
+    let span = (degree..n).rev().find(|&k| knots[k] <= t).unwrap_or(degree);
+    let mut d: Vec<(f32, f32, f32)> = (0..(degree + 1))
+        .map(|j| {
+            let (p, w) = control[j + span - degree];
+            (p.0 * w, p.1 * w, w)
+        })
+        .collect();
+
+    for r in 1..(degree + 1) {
+        for j in (r..(degree + 1)).rev() {
+            let i = j + span - degree;
+            let denom = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denom == 0. { 0. } else { (t - knots[i]) / denom };
+            d[j] = (
+                (1. - alpha) * d[j - 1].0 + alpha * d[j].0,
+                (1. - alpha) * d[j - 1].1 + alpha * d[j].1,
+                (1. - alpha) * d[j - 1].2 + alpha * d[j].2,
+            );
+        }
+    }
+
+    let (x, y, w) = d[degree];
+    Point(x / w, y / w)
+}
+
+/// Samples the spline from its control points and knots, falling back on
+/// its fit points, or on its control polygon
+fn spline(entity: &Entity) -> Option<Polygon> {
+    let degree = entity.int(71).unwrap_or(3).max(1) as usize;
+    let knots = entity.floats(40);
+    let weights = entity.floats(41);
+    let control: Vec<(Point, f32)> = entity
+        .points(10)
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, weights.get(i).cloned().filter(|&w| w > 0.).unwrap_or(1.)))
+        .collect();
+    let closed = entity.is_closed();
+
+    let n = control.len();
+    if n > degree && knots.len() == n + degree + 1 {
+        let (start, end) = (knots[degree], knots[n]);
+        let steps = SPLINE_STEPS * (n - degree);
+        let points = (0..(steps + 1))
+            .map(|i| de_boor(start + (end - start) * i as f32 / steps as f32, degree, &knots, &control))
+            .collect();
+        return Some(polygon(points, closed));
+    }
+
+    let fit = entity.points(11);
+    if fit.len() >= 2 {
+        return Some(polygon(fit, closed));
+    }
+    if n >= 2 {
+        return Some(polygon(control.into_iter().map(|(p, _)| p).collect(), closed));
+    }
+    None
+}
+
+impl<'a> Context<'a> {
+    fn is_hidden(&self, entity: &Entity) -> bool {
+        !self.options.include_hidden && !self.drawing.is_shown(entity.layer())
+    }
+
+    /// Converts a list of entities, or the ones on the given layer, the ones
+    /// drawn end to end being joined
+    fn convert(&mut self, entities: &'a [Entity<'a>], prefix: &str, layer: Option<&str>) -> Vec<Node> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut nodes = Vec::new();
+        for entity in entities {
+            let count = counts.entry(entity.kind).or_insert(0);
+            *count += 1;
+            if layer.is_none_or(|layer| layer == entity.layer()) && !self.is_hidden(entity) {
+                let source = format!("{}/{}[{}]", prefix, entity.kind, count);
+                nodes.extend(self.entity(entity, source));
+            }
+        }
+        chains(nodes, SNAPPING / self.drawing.scale)
+    }
+
+    fn entity(&mut self, entity: &'a Entity<'a>, source: String) -> Vec<Node> {
+        let mut attributes = HashMap::new();
+        if let Some(handle) = entity.get(5) {
+            attributes.insert(String::from("id"), String::from(handle));
+        }
+        attributes.insert(String::from("layer"), String::from(entity.layer()));
+        let meta = Meta {
+            layer: false,
+            source,
+            fill_rule: FillRule::default(),
+            attributes,
+        };
+
+        let tolerance = self.tolerance;
+        let polygon = match entity.kind {
+            "LINE" => polygon(vec![entity.point(10), entity.point(11)], false),
+            "LWPOLYLINE" => lwpolyline(entity, tolerance),
+            // Meshes and polyface meshes aside
+            "POLYLINE" if entity.int(70).unwrap_or(0) & (16 | 64) == 0 => {
+                let vertices: Vec<_> = entity
+                    .vertices
+                    .iter()
+                    .map(|vertex| (vertex.point(10), vertex.float(42).unwrap_or(0.)))
+                    .collect();
+                polyline(&vertices, entity.is_closed(), tolerance)
+            }
+            "ARC" => {
+                let (center, radius) = (entity.point(10), entity.float(40).unwrap_or(0.));
+                let start = entity.float(50).unwrap_or(0.).to_radians();
+                let mut sweep = entity.float(51).unwrap_or(360.).to_radians() - start;
+                sweep = sweep.rem_euclid(2. * PI);
+                if sweep == 0. {
+                    sweep = 2. * PI;
+                }
+                let first = Point(center.0 + radius * start.cos(), center.1 + radius * start.sin());
+                let points = Some(first).into_iter().chain(arc(center, radius, start, sweep, tolerance)).collect();
+                polygon(points, false)
+            }
+            "CIRCLE" => {
+                let (center, radius) = (entity.point(10), entity.float(40).unwrap_or(0.));
+                polygon(arc(center, radius, 0., 2. * PI, tolerance), true)
+            }
+            "ELLIPSE" => ellipse(entity, tolerance),
+            "SPLINE" => match spline(entity) {
+                Some(polygon) => polygon,
+                None => return Vec::new(),
+            },
+            "INSERT" => return self.insert(entity, meta),
+            _ => return Vec::new(),
+        };
+
+        if polygon.points.is_empty() {
+            return Vec::new();
+        }
+        let mut node = Node::Path(meta, vec![polygon]);
+        node.transform(&entity.ocs());
+        vec![node]
+    }
+
+    /// Instantiates the block referenced by an `INSERT`, once per cell of its
+    /// array
+    fn insert(&mut self, entity: &'a Entity<'a>, mut meta: Meta) -> Vec<Node> {
+        let name = match entity.get(2) {
+            Some(name) if !self.stack.contains(&name) => name,
+            _ => return Vec::new(),
+        };
+        let block = match self.drawing.blocks.get(name) {
+            Some(block) => block,
+            None => return Vec::new(),
+        };
+        meta.attributes.insert(String::from("inkscape:label"), String::from(name));
+
+        self.stack.push(name);
+        let children = self.convert(&block.entities, &format!("/BLOCKS/{}", name), None);
+        self.stack.pop();
+        if children.is_empty() {
+            return Vec::new();
+        }
+
+        let origin = entity.point(10);
+        let rotation = Transform::rotate(entity.float(50).unwrap_or(0.));
+        let scale = Transform::scale(entity.float(41).unwrap_or(1.), entity.float(42).unwrap_or(1.))
+            .multiply(&Transform::translate(-block.base.0, -block.base.1));
+        let (columns, rows) = (entity.int(70).unwrap_or(1).max(1), entity.int(71).unwrap_or(1).max(1));
+        let (dx, dy) = (entity.float(44).unwrap_or(0.), entity.float(45).unwrap_or(0.));
+
+        let mut instances = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let transform = entity
+                    .ocs()
+                    .multiply(&Transform::translate(origin.0, origin.1))
+                    .multiply(&rotation)
+                    .multiply(&Transform::translate(column as f32 * dx, row as f32 * dy))
+                    .multiply(&scale);
+                let mut instance = Node::Group(meta.clone(), children.clone());
+                instance.transform(&transform);
+                instances.push(instance);
+            }
+        }
+        instances
+    }
+}
+
+/// Joins the open paths whose ends meet, each set of paths standing in place
+/// of its first one
+fn chains(nodes: Vec<Node>, tolerance: f32) -> Vec<Node> {
+    fn find(parents: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[i] = root;
+        root
+    }
+
+    let ends = |node: &Node| match *node {
+        Node::Path(_, ref polygons) if polygons.len() == 1 && !polygons[0].closed => {
+            let points = &polygons[0].points;
+            Some((points[0], points[points.len() - 1]))
+        }
+        _ => None,
+    };
+
+    let mut parents: Vec<usize> = (0..nodes.len()).collect();
+    let mut grid: Grid<usize> = Grid::new(tolerance.max(1e-3) * 100.);
+    for (i, node) in nodes.iter().enumerate() {
+        let (first, last) = match ends(node) {
+            Some(ends) => ends,
+            None => continue,
+        };
+        for &p in &[first, last] {
+            let bounds = BoundingBox::new(p, p);
+            for (_, other, &j) in grid.query(&bounds.grow(tolerance)) {
+                let q = other.min();
+                if (p.0 - q.0).hypot(p.1 - q.1) <= tolerance {
+                    let (a, b) = (find(&mut parents, j), find(&mut parents, i));
+                    parents[b] = a;
+                }
+            }
+            grid.insert(bounds, i);
+        }
+    }
+
+    let mut chains: Vec<Option<Vec<Node>>> = (0..nodes.len()).map(|_| None).collect();
+    let mut order = Vec::new();
+    for (i, node) in nodes.into_iter().enumerate() {
+        let root = find(&mut parents, i);
+        if chains[root].is_none() {
+            order.push(root);
+        }
+        chains[root].get_or_insert_with(Vec::new).push(node);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|root| chains[root].take())
+        .map(|mut chain| {
+            if chain.len() == 1 {
+                return chain.pop().unwrap();
+            }
+            let meta = chain[0].meta().clone();
+            let polygons = chain
+                .into_iter()
+                .flat_map(|node| match node {
+                    Node::Path(_, polygons) => polygons,
+                    Node::Group(..) => Vec::new(),
+                })
+                .collect();
+            Node::Path(meta, close_contours(polygons, tolerance))
+        })
+        .collect()
+}
+
+impl Node {
+    pub fn from_dxf(input: &str) -> Option<Root> {
+        Node::from_dxf_with_options(input, &ParseOptions::default())
+    }
+
+    /// Converts an ASCII DXF drawing, each layer becoming a layer group. The
+    /// lines and curves drawn end to end are joined in a single path, and the
+    /// block references stand for a group each. Coordinates are converted to
+    /// millimetres when the drawing declares its units.
+    ///
+    /// Parts drawn with separate outlines and holes are best told apart with
+    /// `Grouping::Outline`.
+    pub fn from_dxf_with_options(input: &str, options: &ParseOptions) -> Option<Root> {
+        let pairs = pairs(input)?;
+        let drawing = Drawing::new(&pairs)?;
+
+        // Layers in the order of the table, then of their first entity
+        let mut layers: Vec<&str> = drawing.layers.iter().map(|&(name, _)| name).collect();
+        for entity in &drawing.entities {
+            if !layers.contains(&entity.layer()) {
+                layers.push(entity.layer());
+            }
+        }
+
+        let mut ctx = Context {
+            drawing: &drawing,
+            options: options.clone(),
+            tolerance: CURVE_TOLERANCE / drawing.scale,
+            stack: Vec::new(),
+        };
+
+        let mut nodes = Vec::new();
+        for layer in layers {
+            let children = ctx.convert(&drawing.entities, "/ENTITIES", Some(layer));
+            if children.is_empty() {
+                continue;
+            }
+
+            let mut attributes = HashMap::new();
+            attributes.insert(String::from("inkscape:label"), String::from(layer));
+            attributes.insert(String::from("layer"), String::from(layer));
+            let meta = Meta {
+                layer: true,
+                source: format!("/TABLES/LAYER/{}", layer),
+                fill_rule: FillRule::default(),
+                attributes,
+            };
+            let mut node = Node::Group(meta, children);
+            if drawing.scale != 1. {
+                node.transform(&Transform::scale(drawing.scale, drawing.scale));
+            }
+            nodes.push(node);
+        }
+
+        Some(Root(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use path::Bounding;
+    use svg::Grouping;
+
+    /// Writes a DXF file made of the given sections
+    fn dxf(sections: &[(&str, &[(i32, &str)])]) -> String {
+        let mut input = String::new();
+        for &(name, pairs) in sections {
+            input.push_str(&format!("0\nSECTION\n2\n{}\n", name));
+            for &(code, value) in pairs {
+                input.push_str(&format!("{}\n{}\n", code, value));
+            }
+            input.push_str("0\nENDSEC\n");
+        }
+        input + "0\nEOF\n"
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_entities() {
+        let input = dxf(&[(
+            "ENTITIES",
+            &[
+                (0, "LINE"), (5, "A1"), (8, "Cut"), (10, "0"), (20, "0"), (11, "10"), (21, "0"),
+                (0, "LINE"), (8, "Cut"), (10, "10"), (20, "10"), (11, "10"), (21, "0"),
+                (0, "ARC"), (8, "Cut"), (10, "5"), (20, "10"), (40, "5"), (50, "0"), (51, "180"),
+                (0, "LINE"), (8, "Cut"), (10, "0"), (20, "10"), (11, "0"), (21, "0"),
+                (0, "CIRCLE"), (8, "Cut"), (10, "5"), (20, "5"), (40, "2"),
+                (0, "LINE"), (8, "Engrave"), (10, "20"), (20, "0"), (11, "30"), (21, "0"),
+            ],
+        )]);
+        let root = Node::from_dxf(&input).unwrap();
+        assert_eq!(root.0.len(), 2);
+        assert_eq!(root.0[0].meta().label(), Some("Cut"));
+        assert!(root.0[0].meta().layer);
+
+        // The lines and the arc are joined, the circle is its own path
+        let groups = root.simplify();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].polygons.len(), 1);
+        assert!(groups[0].polygons[0].closed);
+        assert_eq!(groups[0].id, Some(String::from("A1")));
+        assert_eq!(groups[0].source, "/ENTITIES/LINE[1]");
+        assert_eq!(groups[1].source, "/ENTITIES/CIRCLE[1]");
+        assert!(groups[1].polygons[0].closed);
+        // Within the tolerance of the circle along its perimeter
+        assert!((groups[1].polygons[0].signed_area() - 4. * PI).abs() < 4. * PI * CURVE_TOLERANCE);
+        let bounds = groups[0].bounding();
+        assert!(bounds.max().1 > 15. - CURVE_TOLERANCE && bounds.max().1 <= 15.);
+
+        let groups = root.simplify_with(&Grouping::Outline);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].polygons.len(), 2);
+    }
+
+    #[test]
+    fn test_bulge() {
+        let input = dxf(&[(
+            "ENTITIES",
+            &[
+                (0, "LWPOLYLINE"), (90, "2"), (70, "1"),
+                (10, "0"), (20, "0"), (42, "1"),
+                (10, "2"), (20, "0"), (42, "1"),
+                (0, "POLYLINE"), (70, "0"),
+                (0, "VERTEX"), (10, "0"), (20, "5"), (42, "-1"),
+                (0, "VERTEX"), (10, "2"), (20, "5"),
+                (0, "SEQEND"),
+            ],
+        )]);
+        let groups = Node::from_dxf(&input).unwrap().simplify();
+        assert_eq!(groups.len(), 2);
+
+        // Counter-clockwise circle through both vertices
+        let circle = &groups[0].polygons[0];
+        assert!(circle.closed);
+        assert!((circle.signed_area() - PI).abs() < 2. * PI * CURVE_TOLERANCE);
+        assert_close(circle.bounding().min().1, -1.);
+
+        // Clockwise half circle above the chord
+        let arc = &groups[1].polygons[0];
+        assert!(!arc.closed);
+        assert_eq!(arc.points[arc.points.len() - 1], Point(2., 5.));
+        assert_close(arc.bounding().max().1, 6.);
+    }
+
+    #[test]
+    fn test_curves() {
+        let input = dxf(&[(
+            "ENTITIES",
+            &[
+                (0, "ELLIPSE"), (10, "0"), (20, "0"), (11, "2"), (21, "0"), (40, "0.5"),
+                (0, "SPLINE"), (70, "8"), (71, "3"), (72, "8"), (73, "4"),
+                (40, "0"), (40, "0"), (40, "0"), (40, "0"), (40, "1"), (40, "1"), (40, "1"), (40, "1"),
+                (10, "10"), (20, "0"), (10, "11"), (20, "2"), (10, "13"), (20, "2"), (10, "14"), (20, "0"),
+            ],
+        )]);
+        let groups = Node::from_dxf(&input).unwrap().simplify();
+
+        let ellipse = &groups[0].polygons[0];
+        assert!(ellipse.closed);
+        let bounds = ellipse.bounding();
+        assert_close(bounds.max().0, 2.);
+        assert_close(bounds.max().1, 1.);
+
+        let spline = &groups[1].polygons[0];
+        assert!(!spline.closed);
+        assert_eq!(spline.points[0], Point(10., 0.));
+        assert_close(spline.points[spline.points.len() - 1].0, 14.);
+        let middle = spline.points[spline.points.len() / 2];
+        assert_close(middle.0, 12.);
+        assert_close(middle.1, 1.5);
+    }
+
+    #[test]
+    fn test_insert() {
+        let input = dxf(&[
+            ("HEADER", &[(9, "$INSUNITS"), (70, "1")]),
+            (
+                "TABLES",
+                &[
+                    (0, "TABLE"), (2, "LAYER"),
+                    (0, "LAYER"), (2, "Parts"), (62, "7"), (70, "0"),
+                    (0, "LAYER"), (2, "Notes"), (62, "-7"), (70, "0"),
+                    (0, "ENDTAB"),
+                ],
+            ),
+            (
+                "BLOCKS",
+                &[
+                    (0, "BLOCK"), (2, "Washer"), (10, "1"), (20, "1"),
+                    (0, "CIRCLE"), (8, "0"), (10, "1"), (20, "1"), (40, "1"),
+                    (0, "INSERT"), (2, "Washer"),
+                    (0, "ENDBLK"),
+                ],
+            ),
+            (
+                "ENTITIES",
+                &[
+                    (0, "INSERT"), (8, "Parts"), (2, "Washer"), (10, "10"), (20, "0"), (41, "2"), (42, "2"),
+                    (70, "2"), (44, "5"),
+                    (0, "LINE"), (8, "Notes"), (10, "0"), (20, "0"), (11, "1"), (21, "0"),
+                ],
+            ),
+        ]);
+        let root = Node::from_dxf(&input).unwrap();
+        let groups = root.simplify();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].label, Some(String::from("Washer")));
+        assert_eq!(groups[1].source, "/ENTITIES/INSERT[1]");
+
+        // Inches converted to millimetres
+        let (a, b) = (groups[0].bounding(), groups[1].bounding());
+        assert_close(a.min().0, 8. * 25.4);
+        assert_close(a.max().0, 12. * 25.4);
+        assert_close(b.min().0, 13. * 25.4);
+
+        let options = ParseOptions { include_hidden: true };
+        let root = Node::from_dxf_with_options(&input, &options).unwrap();
+        assert_eq!(root.simplify().len(), 3);
+        assert!(Node::from_dxf("0\nSECTION\nfoo").is_none());
+    }
+}
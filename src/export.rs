@@ -0,0 +1,708 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use arc::{Curve, Segment};
+use nest::{Layout, Sheet};
+use offset::OffsetOptions;
+use parser::{escape, Attribute, Content, Element, XMLDoc};
+use part::Part;
+use path::{Point, Polygon};
+use repair::Warning;
+use svg::{attribute, transform};
+use transform::Transform;
+
+/// Options of the SVG export
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Unit of the size of the document: `mm`, `cm`, `in`, `pt` or `px`
+    pub unit: String,
+    /// Length of a user unit of the parts, in `unit`
+    pub scale: f32,
+    /// Width of the lines, in user units
+    pub stroke_width: f32,
+}
+
+impl Default for SvgOptions {
+    /// Parts drawn in CSS pixels, as Inkscape does, exported in millimetres
+    fn default() -> Self {
+        SvgOptions {
+            unit: String::from("mm"),
+            scale: 25.4 / 96.,
+            stroke_width: 0.5,
+        }
+    }
+}
+
+/// A length rounded to a tenth of a micrometre
+pub fn length(value: f32) -> String {
+    let value = format!("{:.4}", value);
+    value.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Path data drawing the polygon
+fn path_data(polygon: &Polygon) -> String {
+    let mut d = String::new();
+    for (i, p) in polygon.points.iter().enumerate() {
+        let _ = write!(d, "{}{},{} ", if i == 0 { "M" } else { "L" }, p.0, p.1);
+    }
+    if polygon.closed {
+        d.push('Z');
+    }
+    d.trim_end().to_string()
+}
+
+/// Writes a sheet as a SVG document, its parts moved into place
+pub fn sheet_to_svg(sheet: &Sheet, parts: &[Part], options: &SvgOptions) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="{}{unit}" height="{}{unit}" viewBox="0 0 {} {}">"#,
+        length(sheet.width * options.scale),
+        length(sheet.height * options.scale),
+        sheet.width,
+        sheet.height,
+        unit = options.unit
+    );
+    let _ = writeln!(
+        svg,
+        r##"  <rect id="sheet" x="0" y="0" width="{}" height="{}" fill="none" stroke="#0000ff" stroke-width="{}" />"##,
+        sheet.width, sheet.height, options.stroke_width
+    );
+
+    // Copies of a part get their own ids
+    let mut copies: HashMap<&str, usize> = HashMap::new();
+    for placement in &sheet.placements {
+        let part = &parts[placement.part];
+
+        let mut attributes = String::new();
+        if let Some(ref id) = part.id {
+            let copy = copies.entry(id).or_insert(0);
+            *copy += 1;
+            let id = if *copy == 1 { id.clone() } else { format!("{}-{}", id, copy) };
+            let _ = write!(attributes, r#" id="{}""#, escape(&id));
+        }
+        if let Some(ref label) = part.label {
+            let _ = write!(attributes, r#" inkscape:label="{}""#, escape(label));
+        }
+        let _ = write!(attributes, r#" data-source="{}""#, escape(&part.source));
+
+        let _ = writeln!(
+            svg,
+            r##"  <g{} transform="{}" fill="none" stroke="#ff0000" stroke-width="{}">"##,
+            attributes,
+            placement.transform(),
+            options.stroke_width
+        );
+        for (polygon, _) in part.polygons() {
+            let _ = writeln!(svg, r#"    <path d="{}" />"#, path_data(polygon));
+        }
+        for polygon in &part.open {
+            let _ = writeln!(svg, r#"    <path d="{}" />"#, path_data(polygon));
+        }
+        let _ = writeln!(svg, "  </g>");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes each sheet of the layout as a SVG document
+pub fn layout_to_svg(layout: &Layout, parts: &[Part], options: &SvgOptions) -> Vec<String> {
+    layout
+        .sheets
+        .iter()
+        .map(|sheet| sheet_to_svg(sheet, parts, options))
+        .collect()
+}
+
+/// The `n`th child element named `name`, counting from 1, by position in
+/// the children
+fn child_position(node: &Element, name: &str, n: usize) -> Option<usize> {
+    node.children
+        .iter()
+        .enumerate()
+        .filter(|&(_, child)| match *child {
+            Content::Element(ref e) => e.name == name,
+            _ => false,
+        })
+        .nth(n.checked_sub(1)?)
+        .map(|(i, _)| i)
+}
+
+/// Finds the element at a location like `/svg/g[1]/path[2]`, returning its
+/// position in the tree along with the transformation of its ancestors, the
+/// root aside
+fn locate(root: &Element, source: &str) -> Option<(Vec<usize>, Transform)> {
+    let mut steps = source.split('/');
+    if steps.next() != Some("") || steps.next() != Some(root.name.as_str()) {
+        return None;
+    }
+
+    let (mut node, mut positions, mut ctm) = (root, Vec::new(), Transform::identity());
+    for step in steps {
+        let (name, n) = step.strip_suffix(']')?.split_once('[')?;
+        let position = child_position(node, name, n.parse().ok()?)?;
+        if !positions.is_empty() {
+            ctm = ctm.multiply(&transform(node).unwrap_or_default());
+        }
+        node = match node.children[position] {
+            Content::Element(ref e) => e,
+            _ => return None,
+        };
+        positions.push(position);
+    }
+
+    Some((positions, ctm))
+}
+
+fn set_attribute(node: &mut Element, name: &str, value: String) {
+    match node.attributes.iter_mut().find(|a| a.name == name) {
+        Some(attr) => attr.value = value,
+        None => node.attributes.push(Attribute {
+            name: String::from(name),
+            value,
+        }),
+    }
+}
+
+/// Appends a suffix to the ids of the element and its descendants
+fn suffix_ids(node: &mut Element, suffix: &str) {
+    if let Some(id) = attribute(node, "id").map(String::from) {
+        set_attribute(node, "id", format!("{}{}", id, suffix));
+    }
+    for child in &mut node.children {
+        if let Content::Element(ref mut e) = *child {
+            suffix_ids(e, suffix);
+        }
+    }
+}
+
+/// Moves the source elements of the parts placed on the sheet into place by
+/// setting their `transform` attribute, the other elements being left as is.
+/// Copies of a part are cloned next to its element, their ids suffixed.
+///
+/// Parts gathered from several elements only move the one they are
+/// attributed to. Returns a warning for each part whose element can't be
+/// moved.
+pub fn place_in_document(doc: &mut XMLDoc, sheet: &Sheet, parts: &[Part]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let mut warn = |source: &str, message: &str| {
+        warnings.push(Warning {
+            source: String::from(source),
+            message: String::from(message),
+        })
+    };
+
+    // Placements of each part, in order
+    let mut placed: Vec<(usize, Vec<Transform>)> = Vec::new();
+    for placement in &sheet.placements {
+        match placed.iter_mut().find(|&&mut (part, _)| part == placement.part) {
+            Some(&mut (_, ref mut transforms)) => transforms.push(placement.transform()),
+            None => placed.push((placement.part, vec![placement.transform()])),
+        }
+    }
+
+    let mut targets: Vec<(Vec<usize>, Vec<Transform>)> = Vec::new();
+    for (part, transforms) in placed {
+        let source = &parts[part].source;
+        let (positions, ctm) = match locate(&doc.root, source) {
+            Some(found) if !found.0.is_empty() => found,
+            _ => {
+                warn(source, "source element not found");
+                continue;
+            }
+        };
+        let inverse = match ctm.inverse() {
+            Some(inverse) => inverse,
+            None => {
+                warn(source, "source element flattened by its ancestors");
+                continue;
+            }
+        };
+        if targets.iter().any(|(p, _)| *p == positions) {
+            warn(source, "source element shared with another part");
+            continue;
+        }
+
+        // The placements apply to the coordinates of the document, the
+        // attribute to those of the parent element
+        let transforms = transforms
+            .iter()
+            .map(|t| inverse.multiply(t).multiply(&ctm))
+            .collect();
+        targets.push((positions, transforms));
+    }
+
+    // Copies only shift the elements following theirs, so the elements are
+    // moved from the end of the document on
+    targets.sort_by(|a, b| b.0.cmp(&a.0));
+    for (positions, transforms) in targets {
+        let (&position, path) = positions.split_last().unwrap();
+        let mut parent = &mut doc.root;
+        for &i in path {
+            parent = match parent.children[i] {
+                Content::Element(ref mut e) => e,
+                _ => unreachable!(),
+            };
+        }
+        let node = match parent.children[position] {
+            Content::Element(ref mut e) => e,
+            _ => unreachable!(),
+        };
+
+        let own = transform(node).unwrap_or_default();
+        let original = node.clone();
+        set_attribute(node, "transform", transforms[0].multiply(&own).to_string());
+        for (copy, t) in transforms.iter().enumerate().skip(1) {
+            let mut clone = original.clone();
+            suffix_ids(&mut clone, &format!("-{}", copy + 1));
+            set_attribute(&mut clone, "transform", t.multiply(&own).to_string());
+            parent.children.insert(position + copy, Content::Element(clone));
+        }
+    }
+
+    warnings
+}
+
+/// Writes each sheet of the layout as a copy of the source document, its
+/// parts moved into place
+pub fn layout_to_documents(doc: &XMLDoc, layout: &Layout, parts: &[Part]) -> (Vec<String>, Vec<Warning>) {
+    let mut warnings = Vec::new();
+    let documents = layout
+        .sheets
+        .iter()
+        .map(|sheet| {
+            let mut doc = doc.clone();
+            warnings.extend(place_in_document(&mut doc, sheet, parts));
+            doc.serialize()
+        })
+        .collect();
+    (documents, warnings)
+}
+
+/// Options of the DXF export
+#[derive(Debug, Clone)]
+pub struct DxfOptions {
+    /// Length of a user unit of the parts, in millimetres
+    pub scale: f32,
+    /// Whether the Y axis of the parts points down, as in SVG documents,
+    /// the sheets being flipped to have it point up
+    pub y_down: bool,
+    /// Distance under which points are taken as lying on an arc, in
+    /// millimetres
+    pub arc_tolerance: f32,
+    /// Layer of the outer boundaries of the parts
+    pub outer_layer: String,
+    /// Layer of the holes of the parts
+    pub inner_layer: String,
+    /// Layer of the open paths, engraved rather than cut
+    pub engrave_layer: String,
+    /// Whether to write each outline as a polyline, its arcs given by the
+    /// bulges of the vertices, rather than as separate LINE and ARC entities
+    pub polylines: bool,
+    /// Width of the cut, in millimetres. The outer boundaries are moved
+    /// out and the holes in by half of it, for the beam to run along them
+    pub kerf: f32,
+}
+
+impl Default for DxfOptions {
+    /// Parts drawn in CSS pixels, as Inkscape does
+    fn default() -> Self {
+        DxfOptions {
+            scale: 25.4 / 96.,
+            y_down: true,
+            arc_tolerance: 0.02,
+            outer_layer: String::from("OUTER"),
+            inner_layer: String::from("INNER"),
+            engrave_layer: String::from("ENGRAVE"),
+            polylines: true,
+            kerf: 0.,
+        }
+    }
+}
+
+/// Writes a group code and its value
+fn pair<T: ::std::fmt::Display>(dxf: &mut String, code: i32, value: T) {
+    let _ = writeln!(dxf, "{:>3}\n{}", code, value);
+}
+
+fn vertex(dxf: &mut String, layer: &str, p: (f32, f32), bulge: f32) {
+    pair(dxf, 0, "VERTEX");
+    pair(dxf, 8, layer);
+    pair(dxf, 10, length(p.0));
+    pair(dxf, 20, length(p.1));
+    if bulge != 0. {
+        pair(dxf, 42, bulge);
+    }
+}
+
+/// Writes the segments of a curve as LINE and ARC entities
+fn segments_to_dxf(dxf: &mut String, curve: &Curve, layer: &str) {
+    let mut start = curve.start;
+    for segment in &curve.segments {
+        let end = segment.end();
+        match *segment {
+            Segment::Line(_) => {
+                pair(dxf, 0, "LINE");
+                pair(dxf, 8, layer);
+                pair(dxf, 10, length(start.0));
+                pair(dxf, 20, length(start.1));
+                pair(dxf, 11, length(end.0));
+                pair(dxf, 21, length(end.1));
+            }
+            Segment::Arc { center, sweep, .. } => {
+                // Arcs run counter-clockwise, from the start angle to the end
+                let angle = |p: Point| (p.1 - center.1).atan2(p.0 - center.0).to_degrees();
+                let (from, to) = if sweep > 0. { (start, end) } else { (end, start) };
+                pair(dxf, 0, "ARC");
+                pair(dxf, 8, layer);
+                pair(dxf, 10, length(center.0));
+                pair(dxf, 20, length(center.1));
+                pair(dxf, 40, length((start.0 - center.0).hypot(start.1 - center.1)));
+                pair(dxf, 50, length(angle(from)));
+                pair(dxf, 51, length(angle(to)));
+            }
+        }
+        start = end;
+    }
+}
+
+/// Writes a polygon as a circle, or as a polyline whose arcs are given by
+/// the bulges of its vertices, or as lines and arcs
+fn polygon_to_dxf(dxf: &mut String, polygon: &Polygon, layer: &str, options: &DxfOptions) {
+    let curve = polygon.fit_arcs(options.arc_tolerance);
+    if let [Segment::Arc { center, .. }] = curve.segments.as_slice() {
+        if curve.segments[0].is_circle() {
+            let radius = (curve.start.0 - center.0).hypot(curve.start.1 - center.1);
+            pair(dxf, 0, "CIRCLE");
+            pair(dxf, 8, layer);
+            pair(dxf, 10, length(center.0));
+            pair(dxf, 20, length(center.1));
+            pair(dxf, 40, length(radius));
+            return;
+        }
+    }
+    if !options.polylines {
+        segments_to_dxf(dxf, &curve, layer);
+        return;
+    }
+
+    pair(dxf, 0, "POLYLINE");
+    pair(dxf, 8, layer);
+    pair(dxf, 66, 1);
+    pair(dxf, 10, 0);
+    pair(dxf, 20, 0);
+    pair(dxf, 70, if curve.closed { 1 } else { 0 });
+    let mut start = curve.start;
+    for segment in &curve.segments {
+        vertex(dxf, layer, (start.0, start.1), segment.bulge());
+        start = segment.end();
+    }
+    if !curve.closed {
+        vertex(dxf, layer, (start.0, start.1), 0.);
+    }
+    pair(dxf, 0, "SEQEND");
+    pair(dxf, 8, layer);
+}
+
+/// The transformation from the coordinates of a sheet to millimetres, with
+/// the Y axis pointing up as on the machines
+pub fn to_millimetres(sheet: &Sheet, scale: f32, y_down: bool) -> Transform {
+    if y_down {
+        Transform::matrix(scale, 0., 0., -scale, 0., sheet.height * scale)
+    } else {
+        Transform::scale(scale, scale)
+    }
+}
+
+/// Writes a sheet as a DXF drawing in millimetres, its parts moved into
+/// place, with a layer for the outer boundaries, one for the holes and one
+/// for the open paths
+pub fn sheet_to_dxf(sheet: &Sheet, parts: &[Part], options: &DxfOptions) -> String {
+    let s = options.scale;
+    let to_mm = to_millimetres(sheet, s, options.y_down);
+
+    let mut dxf = String::new();
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "HEADER");
+    pair(&mut dxf, 9, "$ACADVER");
+    pair(&mut dxf, 1, "AC1009");
+    pair(&mut dxf, 9, "$INSUNITS");
+    pair(&mut dxf, 70, 4);
+    pair(&mut dxf, 9, "$EXTMIN");
+    pair(&mut dxf, 10, 0);
+    pair(&mut dxf, 20, 0);
+    pair(&mut dxf, 9, "$EXTMAX");
+    pair(&mut dxf, 10, length(sheet.width * s));
+    pair(&mut dxf, 20, length(sheet.height * s));
+    pair(&mut dxf, 0, "ENDSEC");
+
+    // Outer cuts in white, inner cuts in red and engraving in green
+    let layers = [(&options.outer_layer, 7), (&options.inner_layer, 1), (&options.engrave_layer, 3)];
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "TABLES");
+    pair(&mut dxf, 0, "TABLE");
+    pair(&mut dxf, 2, "LAYER");
+    pair(&mut dxf, 70, layers.len());
+    for &(name, color) in &layers {
+        pair(&mut dxf, 0, "LAYER");
+        pair(&mut dxf, 2, name);
+        pair(&mut dxf, 70, 0);
+        pair(&mut dxf, 62, color);
+        pair(&mut dxf, 6, "CONTINUOUS");
+    }
+    pair(&mut dxf, 0, "ENDTAB");
+    pair(&mut dxf, 0, "ENDSEC");
+
+    pair(&mut dxf, 0, "SECTION");
+    pair(&mut dxf, 2, "ENTITIES");
+    for placement in &sheet.placements {
+        let mut part = parts[placement.part].clone();
+        part.transform(&placement.transform());
+        if options.kerf > 0. {
+            part = part.offset(options.kerf / 2. / options.scale, &OffsetOptions::default());
+        }
+
+        let cuts = part.cuts();
+        let open = part.open.iter().map(|polygon| (polygon, &options.engrave_layer));
+        for (polygon, layer) in cuts
+            .into_iter()
+            .map(|(polygon, hole)| (polygon, if hole { &options.inner_layer } else { &options.outer_layer }))
+            .chain(open)
+        {
+            let mut polygon = polygon.clone();
+            polygon.transform(&to_mm);
+            polygon_to_dxf(&mut dxf, &polygon, layer, options);
+        }
+    }
+    pair(&mut dxf, 0, "ENDSEC");
+    pair(&mut dxf, 0, "EOF");
+    dxf
+}
+
+/// Writes each sheet of the layout as a DXF drawing
+pub fn layout_to_dxf(layout: &Layout, parts: &[Part], options: &DxfOptions) -> Vec<String> {
+    layout
+        .sheets
+        .iter()
+        .map(|sheet| sheet_to_dxf(sheet, parts, options))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nest::Placement;
+    use parser::XMLDoc;
+    use part::FillRule;
+    use path::{Bounding, Point};
+    use svg::{Node, PolygonGroup};
+
+    #[test]
+    fn test_sheet() {
+        let part = Part::from(PolygonGroup {
+            id: Some(String::from("square")),
+            label: Some(String::from("Square & co")),
+            source: String::from("/svg/path[1]"),
+            quantity: 2,
+            part_in_part: true,
+            fill_rule: FillRule::NonZero,
+            polygons: vec![Polygon {
+                closed: true,
+                points: vec![Point(0., 0.), Point(10., 0.), Point(10., 10.), Point(0., 10.)],
+            }],
+        });
+        let placement = |x: f32| Placement {
+            part: 0,
+            rotation: 90.,
+            x,
+            y: 0.,
+        };
+        let sheet = Sheet {
+            width: 96.,
+            height: 48.,
+            placements: vec![placement(10.), placement(30.)],
+        };
+
+        let svg = sheet_to_svg(&sheet, &[part], &SvgOptions::default());
+        assert!(svg.contains(r#"width="25.4mm""#));
+        assert!(svg.contains(r#"id="square""#));
+        assert!(svg.contains(r#"id="square-2""#));
+        assert!(svg.contains(r#"inkscape:label="Square &amp; co""#));
+
+        // Read back, the parts are where they were placed
+        let doc = XMLDoc::parse(&svg).unwrap();
+        let groups = Node::from_xml_doc(doc).unwrap().simplify();
+        assert_eq!(groups.len(), 2);
+        let bounds = groups[1].bounding();
+        assert!((bounds.min().0 - 20.).abs() < 1e-4 && (bounds.max().0 - 30.).abs() < 1e-4);
+        assert_eq!(groups[1].label, Some(String::from("Square & co")));
+    }
+
+    #[test]
+    fn test_place_in_document() {
+        let source = "<svg>
+            <style>path { stroke: red; }</style>
+            <g inkscape:groupmode='layer' transform='translate(100 0)'>
+                <path id='frame' transform='scale(2)' d='M 0 0 L 5 0 5 5 0 5 z' />
+                <g id='pair'><path id='left' d='M 0 20 L 4 20 4 24 z' /></g>
+                <path id='missing' d='M 0 40 L 1 40 1 41 z' />
+            </g>
+        </svg>";
+        let doc = XMLDoc::parse(source).unwrap();
+        let parts: Vec<_> = Node::from_xml_doc(doc.clone()).unwrap().simplify().into_iter().map(Part::from).collect();
+        let placement = |part: usize, rotation: f32, x: f32, y: f32| Placement { part, rotation, x, y };
+        let sheet = Sheet {
+            width: 200.,
+            height: 200.,
+            placements: vec![placement(0, 90., -90., 0.), placement(1, 0., -100., 50.), placement(0, 0., 0., 100.)],
+        };
+
+        let mut placed = doc.clone();
+        assert!(place_in_document(&mut placed, &sheet, &parts).is_empty());
+        let xml = placed.serialize();
+        assert!(xml.contains("<style>path { stroke: red; }</style>"));
+        assert!(xml.contains(r#"id="frame-2""#));
+        assert!(xml.contains(r#"id="left""#));
+
+        // Read back, the parts are where they were placed
+        let groups = Node::from_xml_doc(XMLDoc::parse(&xml).unwrap()).unwrap().simplify();
+        assert_eq!(groups.len(), 4);
+        let expected = [(0, 0), (0, 2), (1, 1), (2, usize::MAX)];
+        for (group, &(part, placement)) in groups.iter().zip(&expected) {
+            let mut part = parts[part].clone();
+            if let Some(placement) = sheet.placements.get(placement) {
+                part.transform(&placement.transform());
+            }
+            let (a, b) = (group.bounding(), part.bounding());
+            for &(p, q) in &[(a.min(), b.min()), (a.max(), b.max())] {
+                assert!((p.0 - q.0).abs() < 1e-3 && (p.1 - q.1).abs() < 1e-3, "{:?} != {:?}", a, b);
+            }
+        }
+
+        let mut lost = parts.clone();
+        lost[1].source = String::from("/svg/g[1]/g[2]");
+        let warnings = place_in_document(&mut doc.clone(), &sheet, &lost);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].source, "/svg/g[1]/g[2]");
+    }
+
+    #[test]
+    fn test_dxf() {
+        let circle = (0..64)
+            .map(|i| {
+                let a = i as f32 * ::std::f32::consts::PI / 32.;
+                Point(5. + 2. * a.cos(), 5. + 2. * a.sin())
+            })
+            .collect();
+        let part = Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons: vec![
+                Polygon {
+                    closed: true,
+                    points: vec![Point(0., 0.), Point(10., 0.), Point(10., 10.), Point(0., 10.)],
+                },
+                Polygon {
+                    closed: true,
+                    points: circle,
+                },
+                Polygon {
+                    closed: false,
+                    points: vec![Point(1., 1.), Point(3., 1.)],
+                },
+            ],
+        });
+        let sheet = Sheet {
+            width: 100.,
+            height: 50.,
+            placements: vec![Placement {
+                part: 0,
+                rotation: 0.,
+                x: 20.,
+                y: 0.,
+            }],
+        };
+        let options = DxfOptions {
+            scale: 1.,
+            ..DxfOptions::default()
+        };
+
+        let dxf = sheet_to_dxf(&sheet, &[part.clone()], &options);
+        assert!(dxf.contains("CIRCLE"));
+
+        // Read back, with the Y axis pointing up
+        let root = Node::from_dxf(&dxf).unwrap();
+        let layers: Vec<_> = root.0.iter().map(|layer| layer.meta().label()).collect();
+        assert_eq!(layers, vec![Some("OUTER"), Some("INNER"), Some("ENGRAVE")]);
+        let groups = root.simplify();
+        assert_eq!(groups.len(), 3);
+        let square = groups[0].bounding();
+        assert_eq!((square.min(), square.max()), (Point(20., 40.), Point(30., 50.)));
+        let hole = groups[1].bounding();
+        assert!((hole.min().0 - 23.).abs() < 0.02 && (hole.max().1 - 47.).abs() < 0.02);
+        assert_eq!(groups[2].polygons[0].points, vec![Point(21., 49.), Point(23., 49.)]);
+
+        // The beam runs half the kerf away from the material
+        let options = DxfOptions { kerf: 1., ..options };
+        let dxf = sheet_to_dxf(&sheet, &[part], &options);
+        let groups = Node::from_dxf(&dxf).unwrap().simplify();
+        assert_eq!(groups[2].polygons[0].points, vec![Point(21., 49.), Point(23., 49.)]);
+        let square = groups[0].bounding();
+        assert!((square.min().0 - 19.5).abs() < 0.02 && (square.max().1 - 50.5).abs() < 0.02);
+        let hole = groups[1].bounding();
+        assert!((hole.min().0 - 23.5).abs() < 0.02 && (hole.max().1 - 46.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_dxf_arcs() {
+        // A slot, its rounded ends flattened
+        let half = |x: f32, from: f32| {
+            (0..17).map(move |i| {
+                let a = from + i as f32 * ::std::f32::consts::PI / 16.;
+                Point(x + 2. * a.cos(), 2. + 2. * a.sin())
+            })
+        };
+        let points = half(10., -::std::f32::consts::FRAC_PI_2).chain(half(2., ::std::f32::consts::FRAC_PI_2)).collect();
+        let slot = Polygon { closed: true, points };
+        let part = Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::NonZero,
+            polygons: vec![slot.clone()],
+        });
+        let sheet = Sheet {
+            width: 20.,
+            height: 10.,
+            placements: vec![Placement {
+                part: 0,
+                rotation: 0.,
+                x: 0.,
+                y: 0.,
+            }],
+        };
+        let options = DxfOptions {
+            scale: 1.,
+            y_down: false,
+            polylines: false,
+            ..DxfOptions::default()
+        };
+
+        let dxf = sheet_to_dxf(&sheet, &[part], &options);
+        assert!(!dxf.contains("POLYLINE"));
+        assert_eq!(dxf.matches("\n  0\nARC\n").count(), 2);
+        assert_eq!(dxf.matches("\n  0\nLINE\n").count(), 2);
+
+        let groups = Node::from_dxf(&dxf).unwrap().simplify();
+        assert_eq!(groups.len(), 1);
+        let area = groups[0].polygons[0].signed_area().abs();
+        assert!((area - slot.signed_area().abs()).abs() < 0.1, "{}", area);
+    }
+}
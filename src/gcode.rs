@@ -0,0 +1,349 @@
+use std::fmt::Write;
+use arc::{Curve, Segment};
+use export::{length, to_millimetres};
+use nest::{Layout, Sheet};
+use offset::OffsetOptions;
+use part::Part;
+use path::Point;
+use sequence::{report, sequence, CutReport};
+use toolpath::{toolpath, ToolpathOptions};
+
+/// Units of the coordinates and feed rates of a program
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Units {
+    #[default]
+    Millimetres,
+    Inches,
+}
+
+impl Units {
+    /// Length of the unit, in millimetres
+    fn length(self) -> f32 {
+        match self {
+            Units::Millimetres => 1.,
+            Units::Inches => 25.4,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Units::Millimetres => "G21",
+            Units::Inches => "G20",
+        }
+    }
+}
+
+/// Options of the G-code generation
+#[derive(Debug, Clone)]
+pub struct GcodeOptions {
+    /// Length of a user unit of the parts, in millimetres
+    pub scale: f32,
+    /// Whether the Y axis of the parts points down, as in SVG documents,
+    /// the sheets being flipped to have it point up
+    pub y_down: bool,
+    pub units: Units,
+    /// Cutting speed, in units per minute
+    pub feed_rate: f32,
+    /// Time waited after turning the tool on, in seconds
+    pub pierce_delay: f32,
+    /// Laser power or torch current, replacing `{power}` in `tool_on`
+    pub power: f32,
+    pub tool_on: String,
+    pub tool_off: String,
+    /// Start of the program, where `{units}` is replaced with the units
+    /// command, `{width}` and `{height}` with the size of the sheet, and
+    /// `{cut_length}`, `{travel_length}` and `{pierces}` with its report
+    pub header: String,
+    /// End of the program, with the same replacements as the header
+    pub footer: String,
+    /// Distance under which points are taken as lying on an arc, cut with
+    /// `G2`/`G3`, in millimetres. Only lines are cut when zero
+    pub arc_tolerance: f32,
+    /// Whether to reorder the cuts to shorten the rapid moves, rather than
+    /// cutting the parts one after the other
+    pub optimise: bool,
+    /// Leads and bridges added to the contours
+    pub toolpath: ToolpathOptions,
+    /// Width of the cut, in millimetres. The outer boundaries are moved
+    /// out and the holes in by half of it, for the beam to run along them
+    pub kerf: f32,
+}
+
+impl Default for GcodeOptions {
+    /// Parts drawn in CSS pixels, as Inkscape does, cut in millimetres
+    fn default() -> Self {
+        GcodeOptions {
+            scale: 25.4 / 96.,
+            y_down: true,
+            units: Units::Millimetres,
+            feed_rate: 1000.,
+            pierce_delay: 0.,
+            power: 1000.,
+            tool_on: String::from("M3 S{power}"),
+            tool_off: String::from("M5"),
+            header: String::from("G90\n{units}\n"),
+            footer: String::from("G0 X0 Y0\nM2\n"),
+            arc_tolerance: 0.02,
+            optimise: true,
+            toolpath: ToolpathOptions::default(),
+            kerf: 0.,
+        }
+    }
+}
+
+/// What a cut does to the part
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CutKind {
+    Outer,
+    Inner,
+    /// Open path, marking the part rather than cutting it
+    Engrave,
+}
+
+/// A contour to cut, in millimetres
+#[derive(Debug, Clone)]
+pub struct Cut {
+    /// Index of the placement on the sheet
+    pub placement: usize,
+    pub kind: CutKind,
+    /// Index of the cut enclosing this one, which must be cut after it so
+    /// that the part does not move before its holes are done
+    pub parent: Option<usize>,
+    pub curve: Curve,
+}
+
+/// The contours of the parts placed on the sheet, in millimetres, each part
+/// being engraved first, then cut from its innermost contours out
+pub fn sheet_cuts(sheet: &Sheet, parts: &[Part], options: &GcodeOptions) -> Vec<Cut> {
+    let to_mm = to_millimetres(sheet, options.scale, options.y_down);
+    let mut cuts = Vec::new();
+    for (i, placement) in sheet.placements.iter().enumerate() {
+        let mut part = parts[placement.part].clone();
+        part.transform(&placement.transform());
+        if options.kerf > 0. {
+            part = part.offset(options.kerf / 2. / options.scale, &OffsetOptions::default());
+        }
+
+        let polygons = part.polygons();
+        let closed = part.cuts();
+        // Enclosing polygons being listed first, the reverse order cuts
+        // the holes before their parent
+        let first = cuts.len() + part.open.len();
+        let index = |k: usize| first + closed.len() - 1 - k;
+
+        let open = part.open.iter().map(|polygon| {
+            let parent = polygon.points.first().and_then(|&p| {
+                polygons.iter().position(|&(outer, parent)| parent.is_none() && outer.contains(p))
+            });
+            (polygon, CutKind::Engrave, parent.map(index))
+        });
+        let closed = closed.iter().enumerate().rev().map(|(k, &(polygon, hole))| {
+            let kind = if hole { CutKind::Inner } else { CutKind::Outer };
+            (polygon, kind, polygons[k].1.map(index))
+        });
+        for (polygon, kind, parent) in open.chain(closed) {
+            let mut polygon = polygon.clone();
+            polygon.transform(&to_mm);
+            let curve = if options.arc_tolerance > 0. {
+                polygon.fit_arcs(options.arc_tolerance)
+            } else {
+                Curve::from(&polygon)
+            };
+            cuts.push(Cut {
+                placement: i,
+                kind,
+                parent,
+                curve,
+            });
+        }
+    }
+    cuts
+}
+
+/// Replaces the placeholders of a template
+fn template(template: &str, sheet: &Sheet, report: &CutReport, options: &GcodeOptions) -> String {
+    let unit = options.units.length();
+    template
+        .replace("{units}", options.units.code())
+        .replace("{width}", &length(sheet.width * options.scale / unit))
+        .replace("{height}", &length(sheet.height * options.scale / unit))
+        .replace("{cut_length}", &length(report.cut_length / unit))
+        .replace("{travel_length}", &length(report.travel_length / unit))
+        .replace("{pierces}", &report.pierces.to_string())
+        .replace("{power}", &length(options.power))
+}
+
+/// Writes the cuts, in order
+pub fn cuts_to_gcode(cuts: &[Cut], options: &GcodeOptions) -> String {
+    let unit = options.units.length();
+    let xy = |p: Point| format!("X{} Y{}", length(p.0 / unit), length(p.1 / unit));
+    let tool_on = options.tool_on.replace("{power}", &length(options.power));
+
+    let mut gcode = String::new();
+    for cut in cuts {
+        let kind = match cut.kind {
+            CutKind::Outer => "outer",
+            CutKind::Inner => "inner",
+            CutKind::Engrave => "engrave",
+        };
+        let _ = writeln!(gcode, "; Part {}, {}", cut.placement + 1, kind);
+        let _ = writeln!(gcode, "G0 {}", xy(cut.curve.start));
+        let _ = writeln!(gcode, "{}", tool_on);
+        if options.pierce_delay > 0. {
+            let _ = writeln!(gcode, "G4 P{}", length(options.pierce_delay));
+        }
+
+        let mut current = cut.curve.start;
+        for (i, segment) in cut.curve.segments.iter().enumerate() {
+            let _ = match *segment {
+                Segment::Line(end) => write!(gcode, "G1 {}", xy(end)),
+                Segment::Arc { end, center, sweep } => write!(
+                    gcode,
+                    "{} {} I{} J{}",
+                    if sweep < 0. { "G2" } else { "G3" },
+                    xy(end),
+                    length((center.0 - current.0) / unit),
+                    length((center.1 - current.1) / unit)
+                ),
+            };
+            if i == 0 {
+                let _ = write!(gcode, " F{}", length(options.feed_rate));
+            }
+            gcode.push('\n');
+            current = segment.end();
+        }
+        let _ = writeln!(gcode, "{}", options.tool_off);
+    }
+    gcode
+}
+
+/// Writes the program cutting the parts placed on the sheet
+pub fn sheet_to_gcode(sheet: &Sheet, parts: &[Part], options: &GcodeOptions) -> String {
+    let mut cuts = sheet_cuts(sheet, parts, options);
+    if options.optimise {
+        cuts = sequence(&cuts, Point(0., 0.));
+    }
+    let cuts = toolpath(&cuts, &options.toolpath);
+    let report = report(&cuts, Point(0., 0.));
+    let mut gcode = template(&options.header, sheet, &report, options);
+    gcode.push_str(&cuts_to_gcode(&cuts, options));
+    gcode.push_str(&template(&options.footer, sheet, &report, options));
+    gcode
+}
+
+/// Writes a program for each sheet of the layout
+pub fn layout_to_gcode(layout: &Layout, parts: &[Part], options: &GcodeOptions) -> Vec<String> {
+    layout
+        .sheets
+        .iter()
+        .map(|sheet| sheet_to_gcode(sheet, parts, options))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nest::Placement;
+    use part::FillRule;
+    use path::Polygon;
+    use svg::PolygonGroup;
+
+    fn frame() -> Part {
+        let circle = (0..64)
+            .map(|i| {
+                let a = i as f32 * ::std::f32::consts::PI / 32.;
+                Point(5. + 2. * a.cos(), 5. + 2. * a.sin())
+            })
+            .collect();
+        Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons: vec![
+                Polygon {
+                    closed: true,
+                    points: vec![Point(0., 0.), Point(10., 0.), Point(10., 10.), Point(0., 10.)],
+                },
+                Polygon {
+                    closed: true,
+                    points: circle,
+                },
+            ],
+        })
+    }
+
+    fn sheet() -> Sheet {
+        Sheet {
+            width: 100.,
+            height: 50.,
+            placements: vec![Placement {
+                part: 0,
+                rotation: 0.,
+                x: 20.,
+                y: 0.,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_gcode() {
+        let options = GcodeOptions {
+            scale: 1.,
+            pierce_delay: 0.5,
+            header: String::from("G90\n{units}\n; {pierces} pierces\n"),
+            ..GcodeOptions::default()
+        };
+        let gcode = sheet_to_gcode(&sheet(), &[frame()], &options);
+        let lines: Vec<_> = gcode.lines().collect();
+        assert_eq!(&lines[..3], &["G90", "G21", "; 2 pierces"]);
+        assert_eq!(lines.iter().filter(|l| l.starts_with("M3 S1000")).count(), 2);
+        assert_eq!(lines.iter().filter(|l| **l == "G4 P0.5").count(), 2);
+
+        // The hole, a single arc, is cut before the outer boundary
+        let inner = lines.iter().position(|l| l.ends_with("inner")).unwrap();
+        let outer = lines.iter().position(|l| l.ends_with("outer")).unwrap();
+        assert!(inner < outer);
+        assert!(lines[inner + 4].starts_with("G3 ") && lines[inner + 4].ends_with(" F1000"));
+        assert_eq!(lines[inner + 5], "M5");
+        assert_eq!(lines[outer + 1], "G0 X20 Y40");
+        assert_eq!(lines[outer + 4..outer + 8].iter().filter(|l| l.starts_with("G1")).count(), 4);
+        assert!(gcode.ends_with("G0 X0 Y0\nM2\n"));
+    }
+
+    #[test]
+    fn test_kerf() {
+        let options = GcodeOptions {
+            scale: 1.,
+            kerf: 1.,
+            ..GcodeOptions::default()
+        };
+        let cuts = sheet_cuts(&sheet(), &[frame()], &options);
+        assert_eq!(cuts.len(), 2);
+        let (inner, outer) = (&cuts[0], &cuts[1]);
+        assert_eq!((inner.kind, outer.kind), (CutKind::Inner, CutKind::Outer));
+
+        // The hole shrinks to a radius of 1.5, the square grows rounded corners
+        let pi = ::std::f32::consts::PI;
+        assert!((inner.curve.length() - 3. * pi).abs() < 0.05, "{}", inner.curve.length());
+        assert!((outer.curve.length() - (40. + pi)).abs() < 0.05, "{}", outer.curve.length());
+    }
+
+    #[test]
+    fn test_inches() {
+        let options = GcodeOptions {
+            scale: 25.4,
+            units: Units::Inches,
+            arc_tolerance: 0.,
+            optimise: false,
+            header: String::from("{units} ; {width}x{height}\n"),
+            ..GcodeOptions::default()
+        };
+        let gcode = sheet_to_gcode(&sheet(), &[frame()], &options);
+        assert!(gcode.starts_with("G20 ; 100x50\n"));
+        assert!(!gcode.contains("G3"));
+        assert!(gcode.contains("G0 X20 Y50\n"));
+    }
+}
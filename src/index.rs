@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use path::BoundingBox;
+
+/// Uniform grid over bounding boxes, each box being listed in every cell it
+/// covers. Cells should be about as large as the typical box.
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    cell: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    items: Vec<Option<(BoundingBox, T)>>,
+    len: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(cell: f32) -> Self {
+        Grid {
+            cell: if cell > 0. { cell } else { 1. },
+            cells: HashMap::new(),
+            items: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The cells covered by the box
+    fn cells(&self, bounds: &BoundingBox) -> impl Iterator<Item = (i32, i32)> {
+        let cell = |v: f32| (v / self.cell).floor() as i32;
+        let (min, max) = (bounds.min(), bounds.max());
+        let (x0, x1, y0, y1) = (cell(min.0), cell(max.0), cell(min.1), cell(max.1));
+        (x0..(x1 + 1)).flat_map(move |x| (y0..(y1 + 1)).map(move |y| (x, y)))
+    }
+
+    /// Adds a value covering the box, returning the key to remove it with
+    pub fn insert(&mut self, bounds: BoundingBox, value: T) -> usize {
+        let key = self.items.len();
+        for cell in self.cells(&bounds) {
+            self.cells.entry(cell).or_default().push(key);
+        }
+        self.items.push(Some((bounds, value)));
+        self.len += 1;
+        key
+    }
+
+    pub fn remove(&mut self, key: usize) -> Option<T> {
+        let (bounds, value) = self.items.get_mut(key)?.take()?;
+        let cells: Vec<_> = self.cells(&bounds).collect();
+        for cell in cells {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|&k| k != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, key: usize) -> Option<(&BoundingBox, &T)> {
+        self.items.get(key)?.as_ref().map(|(bounds, value)| (bounds, value))
+    }
+
+    /// The values whose box intersects the given one, touching included,
+    /// along with their keys
+    pub fn query(&self, bounds: &BoundingBox) -> Vec<(usize, &BoundingBox, &T)> {
+        let mut keys: Vec<usize> = self.cells(bounds)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flat_map(|keys| keys.iter().cloned())
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|key| self.get(key).map(|(b, value)| (key, b, value)))
+            .filter(|&(_, b, _)| b.intersects(bounds, true))
+            .collect()
+    }
+
+    /// Every value in the grid, along with its key
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &BoundingBox, &T)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(key, item)| item.as_ref().map(|(bounds, value)| (key, bounds, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use path::Point;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> BoundingBox {
+        BoundingBox::new(Point(x, y), Point(x + w, y + h))
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::new(10.);
+        let a = grid.insert(rect(0., 0., 5., 5.), "a");
+        let b = grid.insert(rect(8., 8., 25., 4.), "b");
+        grid.insert(rect(-30., -30., 5., 5.), "c");
+        assert_eq!(grid.len(), 3);
+
+        let found: Vec<_> = grid.query(&rect(4., 4., 5., 5.)).into_iter().map(|(_, _, &v)| v).collect();
+        assert_eq!(found, vec!["a", "b"]);
+        let found: Vec<_> = grid.query(&rect(30., 9., 1., 1.)).into_iter().map(|(key, _, _)| key).collect();
+        assert_eq!(found, vec![b]);
+
+        assert_eq!(grid.remove(a), Some("a"));
+        assert_eq!(grid.remove(a), None);
+        assert_eq!(grid.len(), 2);
+        assert!(grid.query(&rect(0., 0., 1., 1.)).is_empty());
+        assert_eq!(grid.iter().count(), 2);
+    }
+}
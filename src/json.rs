@@ -0,0 +1,732 @@
+use std::fmt;
+use std::str;
+use nest::{Layout, Placement, Sheet};
+use part::{Contour, Hole, Part};
+use path::{Bounding, Point, Polygon};
+
+/// Version of the documents written, and the latest one read
+const VERSION: u32 = 1;
+
+/// Deepest nesting of arrays and objects read
+const MAX_DEPTH: usize = 256;
+
+/// A JSON value, the members of objects kept in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a whole JSON document
+    pub fn parse(input: &str) -> Option<Json> {
+        let mut reader = Reader {
+            input: input.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let value = reader.value()?;
+        reader.whitespace();
+        if reader.position == reader.input.len() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// The value of a member of an object
+    pub fn get(&self, name: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|(n, _)| n == name).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Json::Number(n) => Some(n as f32),
+            _ => None,
+        }
+    }
+
+    /// The value as an index or a count
+    pub fn as_usize(&self) -> Option<usize> {
+        match *self {
+            Json::Number(n) if n >= 0. && n.fract() == 0. => Some(n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Writes the value with `indent` spaces before each of its lines but
+    /// the first, the arrays holding no object on a single line
+    fn write(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // Numbers read from the parts as short as they were written
+            Json::Number(n) if f64::from(n as f32) == n => write!(f, "{}", n as f32),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref values) if values.iter().any(Json::holds_object) => {
+                writeln!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    write!(f, "{:1$}", "", indent + 2)?;
+                    value.write(f, indent + 2)?;
+                    writeln!(f, "{}", if i + 1 < values.len() { "," } else { "" })?;
+                }
+                write!(f, "{:1$}]", "", indent)
+            }
+            Json::Array(ref values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    value.write(f, indent)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref members) if members.is_empty() => write!(f, "{{}}"),
+            Json::Object(ref members) => {
+                writeln!(f, "{{")?;
+                for (i, (name, value)) in members.iter().enumerate() {
+                    write!(f, "{:1$}", "", indent + 2)?;
+                    write_string(f, name)?;
+                    write!(f, ": ")?;
+                    value.write(f, indent + 2)?;
+                    writeln!(f, "{}", if i + 1 < members.len() { "," } else { "" })?;
+                }
+                write!(f, "{:1$}}}", "", indent)
+            }
+        }
+    }
+
+    fn holds_object(&self) -> bool {
+        match *self {
+            Json::Object(_) => true,
+            Json::Array(ref values) => values.iter().any(Json::holds_object),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// Number of arrays and objects being read
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// Skips `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        if self.input[self.position..].starts_with(token.as_bytes()) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.whitespace();
+        match self.peek()? {
+            b'n' if self.eat("null") => Some(Json::Null),
+            b't' if self.eat("true") => Some(Json::Bool(true)),
+            b'f' if self.eat("false") => Some(Json::Bool(false)),
+            b'"' => self.string().map(Json::String),
+            b'[' => self.array(),
+            b'{' => self.object(),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.position;
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') | Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        let number = str::from_utf8(&self.input[start..self.position]).ok()?;
+        number.parse().ok().map(Json::Number)
+    }
+
+    /// Four hexadecimal digits, after `\u`
+    fn code_unit(&mut self) -> Option<u32> {
+        let digits = self.input.get(self.position..(self.position + 4))?;
+        self.position += 4;
+        u32::from_str_radix(str::from_utf8(digits).ok()?, 16).ok()
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.position += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => {
+                    self.position += 1;
+                    let escaped = self.peek()?;
+                    self.position += 1;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.code_unit()?;
+                            // Characters outside the basic plane, as surrogate pairs
+                            if (0xD800..0xDC00).contains(&code) && self.eat("\\u") {
+                                let low = self.code_unit()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + low.checked_sub(0xDC00)?;
+                            }
+                            ::std::char::from_u32(code)?
+                        }
+                        _ => return None,
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                }
+                b => {
+                    bytes.push(b);
+                    self.position += 1;
+                }
+            }
+        }
+        self.position += 1;
+        String::from_utf8(bytes).ok()
+    }
+
+    /// The values of an array, or the members of an object, separated by
+    /// commas until `end`. Fails beyond `MAX_DEPTH` rather than overflowing
+    /// the stack.
+    fn list<T, F: FnMut(&mut Self) -> Option<T>>(&mut self, end: u8, item: F) -> Option<Vec<T>> {
+        if self.depth == MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let items = self.items(end, item);
+        self.depth -= 1;
+        items
+    }
+
+    fn items<T, F: FnMut(&mut Self) -> Option<T>>(&mut self, end: u8, mut item: F) -> Option<Vec<T>> {
+        self.position += 1;
+        let mut items = Vec::new();
+        self.whitespace();
+        if self.peek()? == end {
+            self.position += 1;
+            return Some(items);
+        }
+        loop {
+            items.push(item(self)?);
+            self.whitespace();
+            match self.peek()? {
+                b',' => self.position += 1,
+                b if b == end => {
+                    self.position += 1;
+                    return Some(items);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.list(b']', Reader::value).map(Json::Array)
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.list(b'}', |reader| {
+            reader.whitespace();
+            if reader.peek()? != b'"' {
+                return None;
+            }
+            let name = reader.string()?;
+            reader.whitespace();
+            if !reader.eat(":") {
+                return None;
+            }
+            Some((name, reader.value()?))
+        })
+        .map(Json::Object)
+    }
+}
+
+fn number(n: f32) -> Json {
+    Json::Number(f64::from(n))
+}
+
+fn string(s: &Option<String>) -> Json {
+    s.as_ref().map_or(Json::Null, |s| Json::String(s.clone()))
+}
+
+fn points(polygon: &Polygon) -> Json {
+    Json::Array(polygon.points.iter().map(|p| Json::Array(vec![number(p.0), number(p.1)])).collect())
+}
+
+/// The points of a path, a closed one ending on its first point again
+fn path(polygon: &Polygon) -> Json {
+    let end = if polygon.closed { polygon.points.first() } else { None };
+    Json::Array(polygon.points.iter().chain(end).map(|p| Json::Array(vec![number(p.0), number(p.1)])).collect())
+}
+
+fn contour(contour: &Contour) -> Json {
+    let holes = contour
+        .holes
+        .iter()
+        .map(|hole| {
+            Json::Object(vec![
+                (String::from("polygon"), points(&hole.polygon)),
+                (String::from("islands"), Json::Array(hole.islands.iter().map(self::contour).collect())),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        (String::from("outer"), points(&contour.outer)),
+        (String::from("holes"), Json::Array(holes)),
+    ])
+}
+
+fn part(part: &Part) -> Json {
+    let bounds = part.bounding();
+    let bounds = if bounds.is_empty() {
+        Json::Null
+    } else {
+        Json::Array(bounds.to_rect().iter().map(|&n| number(n)).collect())
+    };
+    Json::Object(vec![
+        (String::from("id"), string(&part.id)),
+        (String::from("label"), string(&part.label)),
+        (String::from("source"), Json::String(part.source.clone())),
+        (String::from("quantity"), Json::Number(f64::from(part.quantity))),
+        (String::from("part_in_part"), Json::Bool(part.part_in_part)),
+        (String::from("bounds"), bounds),
+        (String::from("contours"), Json::Array(part.contours.iter().map(contour).collect())),
+        (String::from("open"), Json::Array(part.open.iter().map(path).collect())),
+    ])
+}
+
+fn layout(layout: &Layout, parts: &[Part]) -> Json {
+    let sheets = layout
+        .sheets
+        .iter()
+        .map(|sheet| {
+            let placements = sheet
+                .placements
+                .iter()
+                .map(|placement| {
+                    Json::Object(vec![
+                        (String::from("part"), Json::Number(placement.part as f64)),
+                        (String::from("id"), parts.get(placement.part).map_or(Json::Null, |p| string(&p.id))),
+                        (String::from("x"), number(placement.x)),
+                        (String::from("y"), number(placement.y)),
+                        (String::from("rotation"), number(placement.rotation)),
+                    ])
+                })
+                .collect();
+            Json::Object(vec![
+                (String::from("width"), number(sheet.width)),
+                (String::from("height"), number(sheet.height)),
+                (String::from("placements"), Json::Array(placements)),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        (String::from("sheets"), Json::Array(sheets)),
+        (
+            String::from("unplaced"),
+            Json::Array(layout.unplaced.iter().map(|&i| Json::Number(i as f64)).collect()),
+        ),
+    ])
+}
+
+fn read_polygon(json: &Json, closed: bool) -> Option<Polygon> {
+    let points = json
+        .as_array()?
+        .iter()
+        .map(|point| match point.as_array()? {
+            [x, y] => Some(Point(x.as_f32()?, y.as_f32()?)),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    Some(Polygon { closed, points })
+}
+
+/// Reads a path, closed when its last point repeats the first one
+fn read_path(json: &Json) -> Option<Polygon> {
+    let mut polygon = read_polygon(json, false)?;
+    if polygon.points.len() > 2 && polygon.points.first() == polygon.points.last() {
+        polygon.points.pop();
+        polygon.closed = true;
+    }
+    Some(polygon)
+}
+
+fn read_contour(json: &Json) -> Option<Contour> {
+    let holes = json
+        .get("holes")?
+        .as_array()?
+        .iter()
+        .map(|hole| {
+            Some(Hole {
+                polygon: read_polygon(hole.get("polygon")?, true)?,
+                islands: hole.get("islands")?.as_array()?.iter().map(read_contour).collect::<Option<_>>()?,
+            })
+        })
+        .collect::<Option<_>>()?;
+    Some(Contour {
+        outer: read_polygon(json.get("outer")?, true)?,
+        holes,
+    })
+}
+
+/// A string member, missing or null when `None`
+fn read_string(json: &Json, name: &str) -> Option<Option<String>> {
+    match json.get(name) {
+        None | Some(Json::Null) => Some(None),
+        Some(value) => value.as_str().map(|s| Some(String::from(s))),
+    }
+}
+
+fn read_part(json: &Json) -> Option<Part> {
+    Some(Part {
+        id: read_string(json, "id")?,
+        label: read_string(json, "label")?,
+        source: read_string(json, "source")?.unwrap_or_default(),
+        quantity: json.get("quantity").map_or(Some(1), Json::as_usize)? as u32,
+        part_in_part: json.get("part_in_part").map_or(Some(true), Json::as_bool)?,
+        contours: json.get("contours")?.as_array()?.iter().map(read_contour).collect::<Option<_>>()?,
+        open: match json.get("open") {
+            Some(open) => open.as_array()?.iter().map(read_path).collect::<Option<_>>()?,
+            None => Vec::new(),
+        },
+    })
+}
+
+/// Reads a placement, the part being found by its index or else its id
+fn read_placement(json: &Json, parts: &[Part]) -> Option<Placement> {
+    let part = match json.get("part").and_then(Json::as_usize) {
+        Some(part) => part,
+        None => {
+            let id = json.get("id")?.as_str()?;
+            parts.iter().position(|part| part.id.as_ref().is_some_and(|p| p == id))?
+        }
+    };
+    if part >= parts.len() {
+        return None;
+    }
+
+    Some(Placement {
+        part,
+        x: json.get("x")?.as_f32()?,
+        y: json.get("y")?.as_f32()?,
+        rotation: json.get("rotation").map_or(Some(0.), Json::as_f32)?,
+    })
+}
+
+fn read_layout(json: &Json, parts: &[Part]) -> Option<Layout> {
+    let sheets = json
+        .get("sheets")?
+        .as_array()?
+        .iter()
+        .map(|sheet| {
+            Some(Sheet {
+                width: sheet.get("width")?.as_f32()?,
+                height: sheet.get("height")?.as_f32()?,
+                placements: sheet
+                    .get("placements")?
+                    .as_array()?
+                    .iter()
+                    .map(|placement| read_placement(placement, parts))
+                    .collect::<Option<_>>()?,
+            })
+        })
+        .collect::<Option<_>>()?;
+    let unplaced = match json.get("unplaced") {
+        Some(unplaced) => unplaced
+            .as_array()?
+            .iter()
+            .map(|part| part.as_usize().filter(|&part| part < parts.len()))
+            .collect::<Option<_>>()?,
+        None => Vec::new(),
+    };
+    Some(Layout { sheets, unplaced })
+}
+
+/// Parts to cut and, once nested, their layout, as stored and exchanged
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub parts: Vec<Part>,
+    pub layout: Option<Layout>,
+    /// Length of a unit of the coordinates, in millimetres
+    pub scale: f32,
+}
+
+impl Default for Job {
+    fn default() -> Self {
+        Job {
+            parts: Vec::new(),
+            layout: None,
+            scale: 1.,
+        }
+    }
+}
+
+impl Job {
+    /// Writes the job as a JSON document:
+    ///
+    /// ```text
+    /// {
+    ///   "version": 1,
+    ///   "scale": millimetres per unit,
+    ///   "parts": [{"id", "label", "source", "quantity", "part_in_part",
+    ///              "bounds": [x, y, width, height],
+    ///              "contours": [{"outer": [[x, y], …],
+    ///                            "holes": [{"polygon", "islands": [contour, …]}, …]}, …],
+    ///              "open": [[[x, y], …], …]}, …],
+    ///   "layout": {"sheets": [{"width", "height",
+    ///                          "placements": [{"part", "id", "x", "y", "rotation"}, …]}, …],
+    ///              "unplaced": [part, …]}
+    /// }
+    /// ```
+    ///
+    /// Closed paths in "open" end on their first point again.
+    /// Placements refer to parts by index, their id being given along.
+    /// Bounds are left out when reading, and a missing scale is 1.
+    pub fn to_json(&self) -> String {
+        let mut members = vec![
+            (String::from("version"), Json::Number(f64::from(VERSION))),
+            (String::from("scale"), number(self.scale)),
+            (String::from("parts"), Json::Array(self.parts.iter().map(part).collect())),
+        ];
+        if let Some(ref layout) = self.layout {
+            members.push((String::from("layout"), self::layout(layout, &self.parts)));
+        }
+        format!("{}\n", Json::Object(members))
+    }
+
+    /// Reads a job written by this version or an earlier one, the part of
+    /// placements lacking an index being found by id
+    pub fn from_json(input: &str) -> Option<Job> {
+        let json = Json::parse(input)?;
+        match json.get("version").and_then(Json::as_usize) {
+            Some(version) if (1..=VERSION as usize).contains(&version) => (),
+            _ => return None,
+        }
+
+        let scale = match json.get("scale") {
+            Some(scale) => scale.as_f32().filter(|&s| s.is_finite() && s > 0.)?,
+            None => 1.,
+        };
+        let parts: Vec<Part> = json.get("parts")?.as_array()?.iter().map(read_part).collect::<Option<_>>()?;
+        let layout = match json.get("layout") {
+            None | Some(Json::Null) => None,
+            Some(layout) => Some(read_layout(layout, &parts)?),
+        };
+        Some(Job { parts, layout, scale })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use part::FillRule;
+    use svg::PolygonGroup;
+
+    #[test]
+    fn test_parse() {
+        let json = Json::parse(r#" {"a": [1, -2.5e1, true, null], "b": "x\"é😀\n", "c": {}} "#).unwrap();
+        assert_eq!(
+            json.get("a"),
+            Some(&Json::Array(vec![Json::Number(1.), Json::Number(-25.), Json::Bool(true), Json::Null]))
+        );
+        assert_eq!(json.get("b").and_then(Json::as_str), Some("x\"é😀\n"));
+        assert_eq!(json.get("c"), Some(&Json::Object(Vec::new())));
+        assert_eq!(Json::parse(&json.to_string()), Some(json));
+
+        assert_eq!(Json::parse("[1, 2"), None);
+        assert_eq!(Json::parse("[1, 2] 3"), None);
+        assert_eq!(Json::parse(r#"{"a" 1}"#), None);
+    }
+
+    fn job() -> Job {
+        let square = |x: f32, size: f32| Polygon {
+            closed: true,
+            points: vec![Point(x, x), Point(x + size, x), Point(x + size, x + size), Point(x, x + size)],
+        };
+        let frame = Part::from(PolygonGroup {
+            id: Some(String::from("frame")),
+            label: Some(String::from("Frame \"A\"")),
+            source: String::from("/svg/path[1]"),
+            quantity: 2,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons: vec![
+                square(0., 10.),
+                square(2., 6.),
+                square(4., 2.),
+                Polygon {
+                    closed: false,
+                    points: vec![Point(0.5, 0.5), Point(1.5, 0.5)],
+                },
+            ],
+        });
+        let mut disc = frame.clone();
+        disc.id = None;
+        disc.part_in_part = false;
+        disc.contours.truncate(1);
+        disc.contours[0].holes.clear();
+        disc.open = vec![square(3., 4.)];
+
+        let layout = Layout {
+            sheets: vec![Sheet {
+                width: 100.,
+                height: 50.,
+                placements: vec![
+                    Placement {
+                        part: 0,
+                        rotation: 90.,
+                        x: 10.,
+                        y: 0.1,
+                    },
+                    Placement {
+                        part: 1,
+                        rotation: 0.,
+                        x: 30.,
+                        y: 0.,
+                    },
+                ],
+            }],
+            unplaced: vec![0],
+        };
+        Job {
+            parts: vec![frame, disc],
+            layout: Some(layout),
+            scale: 25.4 / 96.,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let job = job();
+        let json = job.to_json();
+        assert!(json.starts_with("{\n  \"version\": 1,\n"));
+        assert!(json.contains("\"bounds\": [0, 0, 10, 10]"));
+        assert!(json.contains("\"outer\": [[0, 0], [10, 0], [10, 10], [0, 10]]"));
+
+        let read = Job::from_json(&json).unwrap();
+        assert_eq!(read.scale, job.scale);
+        assert_eq!(read.parts.len(), 2);
+        for (a, b) in read.parts.iter().zip(&job.parts) {
+            assert_eq!((&a.id, &a.label, &a.source), (&b.id, &b.label, &b.source));
+            assert_eq!((a.quantity, a.part_in_part), (b.quantity, b.part_in_part));
+            let polygons = |part: &Part| -> Vec<_> {
+                part.polygons().into_iter().map(|(p, parent)| (p.points.clone(), p.closed, parent)).collect()
+            };
+            assert_eq!(polygons(a), polygons(b));
+            let open = |part: &Part| -> Vec<_> { part.open.iter().map(|p| (p.points.clone(), p.closed)).collect() };
+            assert_eq!(open(a), open(b));
+        }
+        let (a, b) = (read.layout.unwrap(), job.layout.unwrap());
+        assert_eq!(a.sheets[0].placements, b.sheets[0].placements);
+        assert_eq!((a.sheets[0].width, a.sheets[0].height), (100., 50.));
+        assert_eq!(a.unplaced, b.unplaced);
+        assert_eq!(Job::from_json(&Job::from_json(&json).unwrap().to_json()).unwrap().to_json(), json);
+    }
+
+    #[test]
+    fn test_versions() {
+        let json = r#"{
+            "version": 1,
+            "parts": [{"id": "a", "contours": [{"outer": [[0, 0], [1, 0], [0, 1]], "holes": []}]}],
+            "layout": {"sheets": [{"width": 10, "height": 10, "placements": [{"id": "a", "x": 1, "y": 2}]}]}
+        }"#;
+        let job = Job::from_json(json).unwrap();
+        assert_eq!(job.scale, 1.);
+        assert_eq!(job.parts[0].quantity, 1);
+        assert_eq!(job.parts[0].source, "");
+        let placement = &job.layout.unwrap().sheets[0].placements[0];
+        assert_eq!((placement.part, placement.x, placement.rotation), (0, 1., 0.));
+
+        assert!(Job::from_json(&json.replace("\"version\": 1", "\"version\": 2")).is_none());
+        assert!(Job::from_json(&json.replace("\"id\": \"a\", \"x\"", "\"id\": \"b\", \"x\"")).is_none());
+        assert!(Job::from_json(r#"{"parts": []}"#).is_none());
+        assert!(Job::from_json(&json.replace("\"version\": 1,", "\"version\": 1, \"scale\": 0,")).is_none());
+
+        let unplaced = json.replace("}]}]}", "}]}], \"unplaced\": [0]}");
+        assert_eq!(Job::from_json(&unplaced).unwrap().layout.unwrap().unplaced, vec![0]);
+        assert!(Job::from_json(&unplaced.replace("[0]}", "[1]}")).is_none());
+    }
+
+    #[test]
+    fn test_depth() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_some());
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_none());
+        assert!(Json::parse(&"[{\"a\": ".repeat(100_000)).is_none());
+        assert!(Job::from_json(&nested(1_000_000)).is_none());
+    }
+}
@@ -1,25 +1,73 @@
 #[macro_use]
 extern crate nom;
 
+mod arc;
+mod boolean;
+mod cleanup;
+mod collision;
+mod dxf;
+mod export;
+mod gcode;
+mod index;
+mod json;
+mod nest;
+mod offset;
 mod parser;
+mod metrics;
+mod part;
 mod svg;
 mod path;
+mod repair;
+mod sequence;
+mod toolpath;
+mod transform;
 
 use std::mem;
-use std::ffi::CStr;
+use std::ptr;
+use std::slice;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 
+pub use arc::{Curve, Segment};
+pub use boolean::{boolean, difference, intersection, union, xor, Operation};
+pub use cleanup::close_contours;
+pub use collision::collisions;
+pub use export::{
+    layout_to_documents, layout_to_dxf, layout_to_svg, place_in_document, sheet_to_dxf, sheet_to_svg, DxfOptions,
+    SvgOptions,
+};
+pub use gcode::{cuts_to_gcode, layout_to_gcode, sheet_cuts, sheet_to_gcode, Cut, CutKind, GcodeOptions, Units};
+pub use index::Grid;
+pub use json::Job;
+pub use metrics::{convex_hull, min_area_rect, min_width_rect, OrientedRect};
+pub use nest::{nest, Layout, NestOptions, Placement, Sheet};
+pub use offset::{Join, OffsetOptions};
 pub use parser::XMLDoc;
-pub use svg::{Node, Root};
+pub use part::{Contour, FillRule, Hole, Part};
+pub use svg::{Grouping, Meta, Node, ParseOptions, PolygonGroup, Root};
 pub use path::{Bounding, Path};
+pub use repair::Warning;
+pub use sequence::{report, sequence, CutReport};
+pub use toolpath::{toolpath, LeadShape, ToolpathOptions};
+pub use transform::Transform;
+
+/// Distance by which the outlines may grow when simplified
+const TOLERANCE: f32 = 0.1;
+
+/// Distance under which path ends are joined
+const SNAPPING: f32 = 0.01;
 
 #[repr(C)]
 pub struct Drawing {
     size: u32,
     groups: *const Group,
+    warning_count: u32,
+    /// Problems found in the document, like `source: message`
+    warnings: *const *const c_char,
 }
 
 #[repr(C)]
+#[derive(Clone)]
 pub struct Group {
     x: f32,
     y: f32,
@@ -27,47 +75,108 @@ pub struct Group {
     w: f32,
     size: u32,
     polygons: *const Polygon,
+    /// `id` of the source element, or null
+    id: *const c_char,
+    /// `inkscape:label` of the source element, or null
+    label: *const c_char,
+    /// Location of the source element in the document
+    source: *const c_char,
+    /// Area of the material, holes subtracted
+    area: f32,
+    /// Length of the closed outlines
+    perimeter: f32,
+    centroid: Point,
+    hull: Polygon,
+    /// Smallest rotated rectangle enclosing the part
+    min_rect: Polygon,
+    /// Whether other parts may be nested in the holes of this one
+    part_in_part: bool,
+}
+
+/// Position of a group nested on a sheet
+#[repr(C)]
+pub struct Placed {
+    /// Index of the group in the drawing
+    group: u32,
+    /// Index of the sheet
+    sheet: u32,
+    /// Translation moving the group to its place on the sheet
+    x: f32,
+    y: f32,
+}
+
+/// Groups of a drawing nested on sheets, those left out not being listed
+#[repr(C)]
+pub struct Nesting {
+    width: f32,
+    height: f32,
+    /// Number of sheets used
+    sheets: u32,
+    size: u32,
+    placed: *const Placed,
 }
 
 #[repr(C)]
+#[derive(Clone)]
 pub struct Polygon {
     size: u32,
     vertices: *const Point,
+    /// Whether the polygon is a hole in its parent
+    hole: bool,
+    /// False for paths left open, which are never holes nor parents
+    closed: bool,
+    /// Index of the enclosing polygon in the group, or -1
+    parent: i32,
 }
 
 #[repr(C)]
+#[derive(Clone)]
 pub struct Point {
     x: f32,
     y: f32,
 }
 
 impl Drawing {
-    fn from_root(root: Root) -> Self {
-        let groups = root.simplify();
-        let groups: Vec<_> = groups
+    fn from_root(root: Root, grouping: &Grouping) -> Self {
+        let (parts, warnings) = root.to_parts(grouping);
+        Drawing::from_parts(parts, warnings)
+    }
+
+    fn from_parts(parts: Vec<Part>, warnings: Vec<Warning>) -> Self {
+        let groups: Vec<_> = parts
             .into_iter()
-            .map(|group| {
-                let rect = group.bounding().to_rect();
-                let polygons: Vec<_> = group
+            .flat_map(|part| {
+                // A part without any point has zero rectangles
+                let bounds = part.bounding();
+                let rect = if bounds.is_empty() { [0.; 4] } else { bounds.to_rect() };
+                let quantity = part.quantity as usize;
+                let id = c_string(part.id.clone());
+                let label = c_string(part.label.clone());
+                let source = c_string(Some(part.source.clone()));
+
+                // Polygons with an enclosing hole are islands, not holes
+                let tree = part.polygons();
+                let mut holes: Vec<bool> = Vec::with_capacity(tree.len());
+                for &(_, parent) in &tree {
+                    let hole = parent.is_some_and(|p| !holes[p]);
+                    holes.push(hole);
+                }
+
+                let polygons: Vec<_> = tree
                     .into_iter()
-                    .map(|polygon| {
-                        let vertices: Vec<_> = polygon
-                            .points
-                            .into_iter()
-                            .map(|p| Point { x: p.0, y: p.1 })
-                            .collect();
-                        let ret = Polygon {
-                            size: vertices.len() as u32,
-                            vertices: vertices.as_ptr(),
-                        };
-
-                        mem::forget(ret.size);
-                        mem::forget(ret.vertices);
-                        mem::forget(vertices);
-
-                        ret
-                    })
+                    .zip(holes)
+                    .map(|((polygon, parent), hole)| (polygon, hole, parent.map_or(-1, |p| p as i32)))
+                    .chain(part.open.iter().map(|polygon| (polygon, false, -1)))
+                    .map(|(polygon, hole, parent)| Polygon::new(polygon, hole, parent))
                     .collect();
+                let centroid = part.centroid();
+                let min_rect = part.min_area_rect().map_or_else(
+                    || path::Polygon {
+                        closed: true,
+                        points: vec![path::Point(0., 0.); 4],
+                    },
+                    |rect| rect.to_polygon(),
+                );
                 let ret = Group {
                     x: rect[0],
                     y: rect[1],
@@ -75,29 +184,160 @@ impl Drawing {
                     w: rect[3],
                     size: polygons.len() as u32,
                     polygons: polygons.as_ptr(),
+                    id,
+                    label,
+                    source,
+                    area: part.area(),
+                    perimeter: part.perimeter(),
+                    centroid: Point {
+                        x: centroid.0,
+                        y: centroid.1,
+                    },
+                    hull: Polygon::new(&part.convex_hull(), false, -1),
+                    min_rect: Polygon::new(&min_rect, false, -1),
+                    part_in_part: part.part_in_part,
                 };
 
-                mem::forget(ret.size);
-                mem::forget(ret.polygons);
                 mem::forget(polygons);
-                mem::forget(rect);
 
-                ret
+                // Copies share the same polygons
+                vec![ret; quantity]
             })
             .collect();
 
+        let warnings: Vec<_> = warnings
+            .into_iter()
+            .map(|warning| c_string(Some(warning.to_string())))
+            .collect();
         let ret = Drawing {
             size: groups.len() as u32,
             groups: groups.as_ptr(),
+            warning_count: warnings.len() as u32,
+            warnings: warnings.as_ptr(),
         };
 
-        mem::forget(ret.size);
-        mem::forget(ret.groups);
         mem::forget(groups);
+        mem::forget(warnings);
         ret
     }
 }
 
+impl Drawing {
+    /// Rebuilds the parts of the groups, one for each copy
+    unsafe fn to_parts(&self) -> Vec<Part> {
+        slice::from_raw_parts(self.groups, self.size as usize)
+            .iter()
+            .map(|group| group.to_part(0., 0.))
+            .collect()
+    }
+
+    /// Rebuilds the parts of the groups, their copies counted in their
+    /// quantity, along with the index of the part of each group
+    unsafe fn to_counted_parts(&self) -> (Vec<Part>, Vec<usize>) {
+        let groups = slice::from_raw_parts(self.groups, self.size as usize);
+        let mut parts: Vec<Part> = Vec::new();
+        let mut indices = Vec::with_capacity(groups.len());
+        for (i, group) in groups.iter().enumerate() {
+            // Copies follow each other and share their strings
+            match parts.last_mut() {
+                Some(part) if groups[i - 1].source == group.source => part.quantity += 1,
+                _ => parts.push(group.to_part(0., 0.)),
+            }
+            indices.push(parts.len() - 1);
+        }
+        (parts, indices)
+    }
+}
+
+impl Nesting {
+    /// Rebuilds the layout of the `groups` groups of the drawing nested
+    unsafe fn to_layout(&self, groups: usize) -> Layout {
+        let placed = slice::from_raw_parts(self.placed, self.size as usize);
+        let sheets = (0..self.sheets)
+            .map(|sheet| Sheet {
+                width: self.width,
+                height: self.height,
+                placements: placed
+                    .iter()
+                    .filter(|placed| placed.sheet == sheet)
+                    .map(|placed| Placement {
+                        part: placed.group as usize,
+                        rotation: 0.,
+                        x: placed.x,
+                        y: placed.y,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let unplaced = (0..groups)
+            .filter(|&group| !placed.iter().any(|placed| placed.group as usize == group))
+            .collect();
+        Layout { sheets, unplaced }
+    }
+}
+
+impl Group {
+    /// Rebuilds the part from the polygons of the group, moved by `(dx, dy)`
+    unsafe fn to_part(&self, dx: f32, dy: f32) -> Part {
+        let polygons = slice::from_raw_parts(self.polygons, self.size as usize)
+            .iter()
+            .map(|polygon| path::Polygon {
+                closed: polygon.closed,
+                points: slice::from_raw_parts(polygon.vertices, polygon.size as usize)
+                    .iter()
+                    .map(|p| path::Point(p.x + dx, p.y + dy))
+                    .collect(),
+            })
+            .collect();
+
+        Part::from(PolygonGroup {
+            id: rust_string(self.id),
+            label: rust_string(self.label),
+            source: rust_string(self.source).unwrap_or_default(),
+            quantity: 1,
+            part_in_part: self.part_in_part,
+            fill_rule: FillRule::NonZero,
+            polygons,
+        })
+    }
+}
+
+impl Polygon {
+    fn new(polygon: &path::Polygon, hole: bool, parent: i32) -> Self {
+        let vertices: Vec<_> = polygon
+            .points
+            .iter()
+            .map(|p| Point { x: p.0, y: p.1 })
+            .collect();
+        let ret = Polygon {
+            size: vertices.len() as u32,
+            vertices: vertices.as_ptr(),
+            hole,
+            closed: polygon.closed,
+            parent,
+        };
+
+        mem::forget(vertices);
+
+        ret
+    }
+}
+
+/// Leaks a string as a C string, null if absent
+fn c_string(s: Option<String>) -> *const c_char {
+    s.and_then(|s| CString::new(s).ok())
+        .map_or(ptr::null(), |s| s.into_raw() as *const c_char)
+}
+
+/// Copies a C string, absent if null
+unsafe fn rust_string(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn pretty_print(ptr: *const c_char) {
     let input = unsafe { CStr::from_ptr(ptr) };
@@ -117,5 +357,192 @@ pub extern "C" fn parse(ptr: *const c_char) -> Drawing {
     let doc = XMLDoc::parse(input).expect("Failed to parse document");
 
     let root = Node::from_xml_doc(doc).expect("XML doc is not valid SVG");
-    Drawing::from_root(root)
+    Drawing::from_root(root, &Grouping::default())
+}
+
+/// Reads an ASCII DXF drawing, its parts told apart by their outlines
+///
+/// # Safety
+///
+/// `ptr` must be a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn parse_dxf(ptr: *const c_char) -> Drawing {
+    let input = CStr::from_ptr(ptr);
+    let input = input.to_str().expect("Invalid input");
+
+    let root = Node::from_dxf(input).expect("Document is not valid DXF");
+    Drawing::from_root(root, &Grouping::Outline)
+}
+
+/// Reads the parts of a JSON job in millimetres, its layout left aside. A
+/// document which is not a valid job gives no group and a warning.
+///
+/// # Safety
+///
+/// `ptr` must be a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn parse_json(ptr: *const c_char) -> Drawing {
+    let input = CStr::from_ptr(ptr);
+    match input.to_str().ok().and_then(Job::from_json) {
+        Some(job) => {
+            let mut parts = job.parts;
+            if job.scale != 1. {
+                for part in &mut parts {
+                    part.transform(&Transform::scale(job.scale, job.scale));
+                }
+            }
+            Drawing::from_parts(parts, Vec::new())
+        }
+        None => {
+            let warning = Warning {
+                source: String::from("/"),
+                message: String::from("the document is not a valid job"),
+            };
+            Drawing::from_parts(Vec::new(), vec![warning])
+        }
+    }
+}
+
+/// Whether the parts of two groups, moved by `(ax, ay)` and `(bx, by)`, overlap
+///
+/// # Safety
+///
+/// `a` and `b` must point to groups of drawings returned by `parse` or
+/// `parse_dxf`.
+#[no_mangle]
+pub unsafe extern "C" fn overlaps(a: *const Group, ax: f32, ay: f32, b: *const Group, bx: f32, by: f32) -> bool {
+    let (a, b) = (&*a, &*b);
+    if a.x + ax > b.x + bx + b.h || b.x + bx > a.x + ax + a.h
+        || a.y + ay > b.y + by + b.w || b.y + by > a.y + ay + a.w
+    {
+        return false;
+    }
+
+    // Simplified copies, the outlines only growing
+    let (mut a, mut b) = (a.to_part(ax, ay), b.to_part(bx, by));
+    a.cleanup(TOLERANCE);
+    b.cleanup(TOLERANCE);
+    a.overlaps(&b)
+}
+
+/// Nests the groups of a drawing on as few sheets of `width` by `height` as
+/// possible, `spacing` apart, in the holes of each other where allowed
+///
+/// # Safety
+///
+/// `drawing` must have been returned by `parse` or `parse_dxf`.
+#[no_mangle]
+pub unsafe extern "C" fn nest_drawing(drawing: *const Drawing, width: f32, height: f32, spacing: f32) -> Nesting {
+    let options = NestOptions {
+        spacing,
+        ..NestOptions::new(width, height)
+    };
+    let layout = nest(&(*drawing).to_parts(), &options);
+
+    let placed: Vec<_> = layout
+        .sheets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, sheet)| {
+            sheet.placements.iter().map(move |placement| Placed {
+                group: placement.part as u32,
+                sheet: i as u32,
+                x: placement.x,
+                y: placement.y,
+            })
+        })
+        .collect();
+    let ret = Nesting {
+        width,
+        height,
+        sheets: layout.sheets.len() as u32,
+        size: placed.len() as u32,
+        placed: placed.as_ptr(),
+    };
+
+    mem::forget(placed);
+    ret
+}
+
+/// Writes a sheet of a nesting as `svg`, `dxf` or `gcode`, a user unit of
+/// the drawing being `scale` millimetres. Returns null for an unknown
+/// format or sheet; the text must be released with `free_string`.
+///
+/// # Safety
+///
+/// `nesting` must have been returned by `nest_drawing` for `drawing`, and
+/// `format` must be a null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn write_sheet(
+    drawing: *const Drawing,
+    nesting: *const Nesting,
+    sheet: u32,
+    format: *const c_char,
+    scale: f32,
+    y_down: bool,
+) -> *mut c_char {
+    let parts = (*drawing).to_parts();
+    let layout = (*nesting).to_layout(parts.len());
+    let sheet = match layout.sheets.get(sheet as usize) {
+        Some(sheet) => sheet,
+        None => return ptr::null_mut(),
+    };
+
+    let text = match CStr::from_ptr(format).to_str() {
+        Ok("svg") => sheet_to_svg(sheet, &parts, &SvgOptions {
+            scale,
+            ..SvgOptions::default()
+        }),
+        Ok("dxf") => sheet_to_dxf(sheet, &parts, &DxfOptions {
+            scale,
+            y_down,
+            ..DxfOptions::default()
+        }),
+        Ok("gcode") => sheet_to_gcode(sheet, &parts, &GcodeOptions {
+            scale,
+            y_down,
+            ..GcodeOptions::default()
+        }),
+        _ => return ptr::null_mut(),
+    };
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Writes the parts of a drawing, their copies counted in their quantity, and
+/// the nesting if not null, as a JSON job whose units are `scale` millimetres
+/// long. The text must be released with `free_string`.
+///
+/// # Safety
+///
+/// `drawing` must have been returned by `parse`, `parse_dxf` or
+/// `parse_json`, and `nesting` be null or returned by `nest_drawing` for it.
+#[no_mangle]
+pub unsafe extern "C" fn write_json(drawing: *const Drawing, nesting: *const Nesting, scale: f32) -> *mut c_char {
+    let (parts, indices) = (*drawing).to_counted_parts();
+    let layout = if nesting.is_null() {
+        None
+    } else {
+        let mut layout = (*nesting).to_layout(indices.len());
+        for placement in layout.sheets.iter_mut().flat_map(|sheet| sheet.placements.iter_mut()) {
+            placement.part = indices[placement.part];
+        }
+        for part in &mut layout.unplaced {
+            *part = indices[*part];
+        }
+        Some(layout)
+    };
+    let job = Job { parts, layout, scale };
+    CString::new(job.to_json()).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Releases a string returned by the library
+///
+/// # Safety
+///
+/// `s` must be null or have been returned by the library, and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
 }
@@ -0,0 +1,350 @@
+use std::f32;
+use part::Part;
+use path::{Point, Polygon};
+
+/// A rectangle whose width runs `angle` degrees counter-clockwise from the X
+/// axis, the angle lying in `[0, 90)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrientedRect {
+    pub center: Point,
+    pub width: f32,
+    pub height: f32,
+    pub angle: f32,
+}
+
+impl OrientedRect {
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+
+    /// Corners of the rectangle, counter-clockwise
+    pub fn to_polygon(&self) -> Polygon {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let (w, h) = (self.width / 2., self.height / 2.);
+        let corner = |x: f32, y: f32| {
+            Point(self.center.0 + x * cos - y * sin, self.center.1 + x * sin + y * cos)
+        };
+
+        Polygon {
+            closed: true,
+            points: vec![corner(-w, -h), corner(w, -h), corner(w, h), corner(-w, h)],
+        }
+    }
+
+    /// The rectangle spanning `[min, max]` in the frame whose X axis runs
+    /// along the unit `direction`
+    fn from_frame(direction: Point, min: Point, max: Point) -> OrientedRect {
+        let (u, v) = ((min.0 + max.0) / 2., (min.1 + max.1) / 2.);
+        let rect = OrientedRect {
+            center: Point(u * direction.0 - v * direction.1, u * direction.1 + v * direction.0),
+            width: max.0 - min.0,
+            height: max.1 - min.1,
+            angle: direction.1.atan2(direction.0).to_degrees(),
+        };
+        rect.normalized()
+    }
+
+    /// The bounding rectangle of the points along the unit `direction`
+    fn along(points: &[Point], direction: Point) -> OrientedRect {
+        let (mut min, mut max) = (Point(f32::MAX, f32::MAX), Point(f32::MIN, f32::MIN));
+        for &p in points {
+            let (u, v) = (dot(p, direction), dot(p, normal(direction)));
+            min = Point(min.0.min(u), min.1.min(v));
+            max = Point(max.0.max(u), max.1.max(v));
+        }
+        OrientedRect::from_frame(direction, min, max)
+    }
+
+    /// Brings the angle into `[0, 90)`, swapping the sides as needed
+    fn normalized(mut self) -> OrientedRect {
+        self.angle = self.angle.rem_euclid(180.);
+        if self.angle >= 90. {
+            self.angle -= 90.;
+            ::std::mem::swap(&mut self.width, &mut self.height);
+        }
+        self
+    }
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+/// The direction turned a quarter counter-clockwise
+fn normal(direction: Point) -> Point {
+    Point(-direction.1, direction.0)
+}
+
+fn unit(a: Point, b: Point) -> Point {
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+    Point((b.0 - a.0) / length, (b.1 - a.1) / length)
+}
+
+/// Convex hull of the points, counter-clockwise, using the monotone chain
+pub fn convex_hull(points: &[Point]) -> Polygon {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    points.dedup();
+    if points.len() < 3 {
+        return Polygon { closed: true, points };
+    }
+
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &p in &points {
+            while hull.len() >= start + 2 && hull[hull.len() - 2].cross(hull[hull.len() - 1], p) <= 0. {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point starts the other chain
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+
+    Polygon { closed: true, points: hull }
+}
+
+/// The rectangles enclosing a counter-clockwise convex hull with a side along
+/// each of its edges, found with rotating calipers
+fn calipers(hull: &[Point]) -> Vec<OrientedRect> {
+    let n = hull.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    if n < 3 {
+        let direction = if n == 2 && hull[0] != hull[1] {
+            unit(hull[0], hull[1])
+        } else {
+            Point(1., 0.)
+        };
+        return vec![OrientedRect::along(hull, direction)];
+    }
+
+    // Points furthest from the edge, and furthest along it either way
+    let (mut far, mut right, mut left) = (1, 1, 0);
+    let mut rects = Vec::with_capacity(n);
+    for i in 0..n {
+        let a = hull[i];
+        let direction = unit(a, hull[(i + 1) % n]);
+        let normal = normal(direction);
+
+        while dot(hull[(far + 1) % n], normal) > dot(hull[far], normal) {
+            far = (far + 1) % n;
+        }
+        while dot(hull[(right + 1) % n], direction) > dot(hull[right], direction) {
+            right = (right + 1) % n;
+        }
+        if i == 0 {
+            left = far;
+        }
+        while dot(hull[(left + 1) % n], direction) < dot(hull[left], direction) {
+            left = (left + 1) % n;
+        }
+
+        rects.push(OrientedRect::from_frame(
+            direction,
+            Point(dot(hull[left], direction), dot(a, normal)),
+            Point(dot(hull[right], direction), dot(hull[far], normal)),
+        ));
+    }
+    rects
+}
+
+/// Smallest rectangle enclosing the points, if there are any
+pub fn min_area_rect(points: &[Point]) -> Option<OrientedRect> {
+    calipers(&convex_hull(points).points)
+        .into_iter()
+        .min_by(|a, b| a.area().total_cmp(&b.area()))
+}
+
+/// Rectangle enclosing the points with the shortest side, that is across the
+/// narrowest direction of the points, if there are any
+pub fn min_width_rect(points: &[Point]) -> Option<OrientedRect> {
+    let width = |rect: &OrientedRect| rect.width.min(rect.height);
+    calipers(&convex_hull(points).points)
+        .into_iter()
+        .min_by(|a, b| width(a).total_cmp(&width(b)))
+}
+
+impl Polygon {
+    /// Center of mass of the surface of the polygon, or the average of its
+    /// points if it has no area
+    pub fn centroid(&self) -> Point {
+        let (mut x, mut y, mut area) = (0f64, 0f64, 0f64);
+        for (a, b) in self.edges() {
+            let (a0, a1, b0, b1) = (f64::from(a.0), f64::from(a.1), f64::from(b.0), f64::from(b.1));
+            let cross = a0 * b1 - b0 * a1;
+            x += (a0 + b0) * cross;
+            y += (a1 + b1) * cross;
+            area += cross;
+        }
+
+        if area == 0. {
+            let n = self.points.len().max(1) as f32;
+            let sum = self.points.iter().fold(Point(0., 0.), |s, p| Point(s.0 + p.0, s.1 + p.1));
+            return Point(sum.0 / n, sum.1 / n);
+        }
+        Point((x / (3. * area)) as f32, (y / (3. * area)) as f32)
+    }
+
+    /// Length of the outline, including the closing edge of closed polygons
+    pub fn perimeter(&self) -> f32 {
+        let n = self.points.len();
+        self.edges()
+            .take(if self.closed { n } else { n.saturating_sub(1) })
+            .map(|(a, b)| (b.0 - a.0).hypot(b.1 - a.1))
+            .sum()
+    }
+
+    pub fn convex_hull(&self) -> Polygon {
+        convex_hull(&self.points)
+    }
+
+    pub fn min_area_rect(&self) -> Option<OrientedRect> {
+        min_area_rect(&self.points)
+    }
+
+    pub fn min_width_rect(&self) -> Option<OrientedRect> {
+        min_width_rect(&self.points)
+    }
+}
+
+impl Part {
+    /// Area of the material, holes subtracted
+    pub fn area(&self) -> f32 {
+        self.polygons().iter().map(|&(polygon, _)| polygon.signed_area()).sum()
+    }
+
+    /// Center of mass of the material
+    pub fn centroid(&self) -> Point {
+        let (mut x, mut y, mut area) = (0., 0., 0.);
+        for (polygon, _) in self.polygons() {
+            let (c, a) = (polygon.centroid(), polygon.signed_area());
+            x += c.0 * a;
+            y += c.1 * a;
+            area += a;
+        }
+
+        if area == 0. {
+            return self.convex_hull().centroid();
+        }
+        Point(x / area, y / area)
+    }
+
+    /// Length of the closed outlines, holes included
+    pub fn perimeter(&self) -> f32 {
+        self.polygons().iter().map(|&(polygon, _)| polygon.perimeter()).sum()
+    }
+
+    /// Convex hull of the outer boundaries and open paths
+    pub fn convex_hull(&self) -> Polygon {
+        let points: Vec<Point> = self.contours
+            .iter()
+            .map(|contour| &contour.outer)
+            .chain(&self.open)
+            .flat_map(|polygon| polygon.points.iter().cloned())
+            .collect();
+        convex_hull(&points)
+    }
+
+    pub fn min_area_rect(&self) -> Option<OrientedRect> {
+        min_area_rect(&self.convex_hull().points)
+    }
+
+    pub fn min_width_rect(&self) -> Option<OrientedRect> {
+        min_width_rect(&self.convex_hull().points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use part::FillRule;
+    use svg::PolygonGroup;
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon {
+        Polygon {
+            closed: true,
+            points: points.iter().map(|&(x, y)| Point(x, y)).collect(),
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_polygon() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        assert_eq!(square.centroid(), Point(1., 1.));
+        assert_eq!(square.perimeter(), 8.);
+
+        let hull = polygon(&[(0., 0.), (2., 0.), (1., 1.), (2., 2.), (0., 2.), (1., 0.)]).convex_hull();
+        assert_eq!(hull.points, square.points);
+    }
+
+    #[test]
+    fn test_min_area_rect() {
+        // A 4 by 2 rectangle turned by 30 degrees
+        let rect = OrientedRect {
+            center: Point(5., 5.),
+            width: 4.,
+            height: 2.,
+            angle: 30.,
+        };
+        let found = rect.to_polygon().min_area_rect().unwrap();
+        assert_close(found.area(), 8.);
+        assert_close(found.angle, 30.);
+        assert_close(found.width, 4.);
+        assert_close(found.center.0, 5.);
+
+        let upright = polygon(&[(0., 0.), (1., 0.), (1., 3.), (0., 3.)]).min_area_rect().unwrap();
+        assert_close(upright.angle, 0.);
+        assert_close(upright.height, 3.);
+    }
+
+    #[test]
+    fn test_min_width_rect() {
+        // A parallelogram, narrowest across its long sides
+        let parallelogram = polygon(&[(0., 0.), (4., 0.), (6., 1.), (2., 1.)]);
+        let rect = parallelogram.min_width_rect().unwrap();
+        assert_close(rect.angle, 0.);
+        assert_close(rect.height, 1.);
+        assert_close(rect.width, 6.);
+        assert!(parallelogram.min_area_rect().unwrap().area() <= rect.area());
+
+        let segment = polygon(&[(0., 0.), (1., 1.)]).min_area_rect().unwrap();
+        assert_close(segment.angle, 45.);
+        assert_close(segment.height, 0.);
+
+        assert_eq!(polygon(&[]).min_area_rect(), None);
+        assert_eq!(min_width_rect(&[]), None);
+    }
+
+    #[test]
+    fn test_part() {
+        let part = Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons: vec![
+                polygon(&[(0., 0.), (4., 0.), (4., 4.), (0., 4.)]),
+                polygon(&[(2., 1.), (3., 1.), (3., 3.), (2., 3.)]),
+            ],
+        });
+
+        assert_eq!(part.area(), 14.);
+        assert_eq!(part.perimeter(), 22.);
+        let centroid = part.centroid();
+        assert_close(centroid.0, (16. * 2. - 2. * 2.5) / 14.);
+        assert_close(centroid.1, 2.);
+        assert_eq!(part.min_area_rect().unwrap().area(), 16.);
+    }
+}
@@ -0,0 +1,399 @@
+use std::iter;
+use index::Grid;
+use offset::OffsetOptions;
+use part::Part;
+use path::{segments_intersect, Bounding, BoundingBox, Point, Polygon};
+use transform::Transform;
+
+/// Options of the nesting
+#[derive(Debug, Clone)]
+pub struct NestOptions {
+    /// Width of the sheets
+    pub width: f32,
+    /// Height of the sheets
+    pub height: f32,
+    /// Minimal distance kept between two parts
+    pub spacing: f32,
+    /// Nest parts in the holes of the parts already placed
+    pub part_in_part: bool,
+    /// Distance by which the outlines may grow, and the holes shrink, when
+    /// simplified for the nesting
+    pub tolerance: f32,
+    /// Turn the parts first so that their smallest enclosing rectangle lies
+    /// along the sheet
+    pub orient: bool,
+}
+
+impl NestOptions {
+    pub fn new(width: f32, height: f32) -> Self {
+        NestOptions {
+            width,
+            height,
+            spacing: 0.,
+            part_in_part: true,
+            tolerance: 0.1,
+            orient: false,
+        }
+    }
+}
+
+/// Position of a copy of a part on a sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// Index of the part in the nested list
+    pub part: usize,
+    /// Rotation applied to the part around the origin, in degrees
+    pub rotation: f32,
+    /// Translation applied to the part after its rotation
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Placement {
+    pub fn transform(&self) -> Transform {
+        Transform::translate(self.x, self.y).multiply(&Transform::rotate(self.rotation))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sheet {
+    pub width: f32,
+    pub height: f32,
+    pub placements: Vec<Placement>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    pub sheets: Vec<Sheet>,
+    /// Parts too large for the sheets, once per copy
+    pub unplaced: Vec<usize>,
+}
+
+/// Whether the polygon encloses the rectangle
+fn encloses(polygon: &Polygon, rect: &BoundingBox) -> bool {
+    let corners = rect.corners();
+    if !corners.iter().all(|&corner| polygon.contains(corner)) {
+        return false;
+    }
+
+    let sides = (0..4).map(|i| (corners[i], corners[(i + 1) % 4]));
+    !sides
+        .flat_map(|(a1, a2)| polygon.edges().map(move |(b1, b2)| (a1, a2, b1, b2)))
+        .any(|(a1, a2, b1, b2)| segments_intersect(a1, a2, b1, b2))
+}
+
+/// Parts placed on a sheet being filled
+struct Nester {
+    placements: Vec<Placement>,
+    /// Bounds of the parts placed, along with their holes in which other
+    /// parts may be nested, shrunk by the spacing
+    placed: Grid<Vec<Polygon>>,
+}
+
+impl Nester {
+    fn new(cell: f32) -> Self {
+        Nester {
+            placements: Vec::new(),
+            placed: Grid::new(cell),
+        }
+    }
+
+    /// Whether a part can be placed within the given box
+    fn fits(&self, rect: &BoundingBox, options: &NestOptions) -> bool {
+        if rect.min().0 < 0. || rect.min().1 < 0. || rect.max().0 > options.width
+            || rect.max().1 > options.height
+        {
+            return false;
+        }
+
+        let margin = rect.grow(options.spacing);
+        self.placed.query(&margin).into_iter().all(|(_, bounds, holes)| {
+            !bounds.intersects(&margin, false) || holes.iter().any(|hole| encloses(hole, rect))
+        })
+    }
+
+    /// Finds where to put the top-left corner of a part of the given size,
+    /// growing the used area as little as possible, downwards first
+    fn find_spot(&self, size: &BoundingBox, options: &NestOptions) -> Option<Point> {
+        let (w, h, s) = (size.width(), size.height(), options.spacing);
+
+        let mut candidates = vec![Point(0., 0.)];
+        for (_, bounds, holes) in self.placed.iter() {
+            let (min, max) = (bounds.min(), bounds.max());
+            candidates.push(Point(max.0 + s, min.1));
+            candidates.push(Point(min.0, max.1 + s));
+            candidates.push(Point(max.0 + s, 0.));
+            candidates.push(Point(0., max.1 + s));
+
+            for hole in holes {
+                let bounds = hole.bounding();
+                let (free_w, free_h) = (bounds.width() - w, bounds.height() - h);
+                if free_w < 0. || free_h < 0. {
+                    continue;
+                }
+
+                // Scan the box of the hole, as its corners may lie outside of it
+                let steps = 8;
+                for i in 0..(steps + 1) {
+                    for j in 0..(steps + 1) {
+                        candidates.push(Point(
+                            bounds.min().0 + free_w * i as f32 / steps as f32,
+                            bounds.min().1 + free_h * j as f32 / steps as f32,
+                        ));
+                    }
+                }
+            }
+        }
+
+        let envelope = self.placed.iter().fold(Point(0., 0.), |envelope, (_, bounds, _)| {
+            let max = bounds.max();
+            Point(f32::max(envelope.0, max.0), f32::max(envelope.1, max.1))
+        });
+        let score = |p: &Point| {
+            (
+                f32::max(envelope.1, p.1 + h),
+                f32::max(envelope.0, p.0 + w),
+                p.1,
+                p.0,
+            )
+        };
+
+        candidates
+            .into_iter()
+            .filter(|&p| self.fits(&BoundingBox::new(p, Point(p.0 + w, p.1 + h)), options))
+            .min_by(|a, b| {
+                let (a, b) = (score(a), score(b));
+                (a.0.total_cmp(&b.0))
+                    .then(a.1.total_cmp(&b.1))
+                    .then(a.2.total_cmp(&b.2))
+                    .then(a.3.total_cmp(&b.3))
+            })
+    }
+
+    fn place(&mut self, index: usize, part: &Part, rotation: f32, bounds: &BoundingBox, spot: Point, options: &NestOptions) {
+        let (dx, dy) = (spot.0 - bounds.min().0, spot.1 - bounds.min().1);
+
+        let holes = if options.part_in_part && part.part_in_part {
+            part.holes()
+                .into_iter()
+                .flat_map(|hole| hole.offset(options.spacing, &OffsetOptions::default()))
+                .map(|mut hole| {
+                    hole.translate(dx, dy);
+                    hole
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.placed.insert(bounds.translate(dx, dy), holes);
+        self.placements.push(Placement {
+            part: index,
+            rotation,
+            x: dx,
+            y: dy,
+        });
+    }
+}
+
+/// Rotation bringing the smallest enclosing rectangle of the part along the
+/// axes, across the sheet if it only fits that way
+fn orientation(part: &Part, options: &NestOptions) -> f32 {
+    let rect = match part.min_area_rect() {
+        Some(rect) => rect,
+        None => return 0.,
+    };
+    let fits = |width: f32, height: f32| width <= options.width && height <= options.height;
+    if !fits(rect.width, rect.height) && fits(rect.height, rect.width) {
+        90. - rect.angle
+    } else {
+        -rect.angle
+    }
+}
+
+/// Places every copy of the parts on as few sheets as possible, the largest
+/// parts first
+pub fn nest(parts: &[Part], options: &NestOptions) -> Layout {
+    let rotations: Vec<f32> = parts
+        .iter()
+        .map(|part| if options.orient { orientation(part, options) } else { 0. })
+        .collect();
+    let parts: Vec<Part> = parts
+        .iter()
+        .zip(&rotations)
+        .map(|(part, &rotation)| {
+            let mut part = part.clone();
+            part.cleanup(options.tolerance);
+            if rotation != 0. {
+                part.transform(&Transform::rotate(rotation));
+            }
+            part
+        })
+        .collect();
+
+    let bounds: Vec<BoundingBox> = parts.iter().map(Bounding::bounding).collect();
+    let area = |i: usize| bounds[i].width() * bounds[i].height();
+
+    // Index cells about as large as the average part
+    let sizes: Vec<f32> = bounds
+        .iter()
+        .filter(|bounds| !bounds.is_empty())
+        .map(|bounds| bounds.width().max(bounds.height()))
+        .collect();
+    let cell = sizes.iter().sum::<f32>() / sizes.len().max(1) as f32 + options.spacing;
+
+    let mut order: Vec<usize> = (0..parts.len())
+        .flat_map(|i| iter::repeat_n(i, parts[i].quantity as usize))
+        .collect();
+    order.sort_by(|&a, &b| area(b).total_cmp(&area(a)));
+
+    let mut layout = Layout::default();
+    let mut nesters: Vec<Nester> = Vec::new();
+    for i in order {
+        let size = &bounds[i];
+        if size.is_empty() || size.width() > options.width || size.height() > options.height {
+            layout.unplaced.push(i);
+            continue;
+        }
+
+        let spot = nesters
+            .iter()
+            .enumerate()
+            .filter_map(|(n, nester)| nester.find_spot(size, options).map(|spot| (n, spot)))
+            .next();
+
+        let (n, spot) = match spot {
+            Some(spot) => spot,
+            None => {
+                nesters.push(Nester::new(cell));
+                (nesters.len() - 1, Point(0., 0.))
+            }
+        };
+
+        nesters[n].place(i, &parts[i], rotations[i], size, spot, options);
+    }
+
+    layout.sheets = nesters
+        .into_iter()
+        .map(|nester| Sheet {
+            width: options.width,
+            height: options.height,
+            placements: nester.placements,
+        })
+        .collect();
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics::OrientedRect;
+    use part::FillRule;
+    use svg::PolygonGroup;
+
+    fn square(x: f32, y: f32, size: f32) -> Polygon {
+        Polygon {
+            closed: true,
+            points: vec![
+                Point(x, y),
+                Point(x + size, y),
+                Point(x + size, y + size),
+                Point(x, y + size),
+            ],
+        }
+    }
+
+    fn part(polygons: Vec<Polygon>, quantity: u32) -> Part {
+        Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity,
+            part_in_part: true,
+            fill_rule: FillRule::EvenOdd,
+            polygons,
+        })
+    }
+
+    fn placed_bounds(parts: &[Part], placement: &Placement) -> BoundingBox {
+        let mut part = parts[placement.part].clone();
+        part.transform(&placement.transform());
+        part.bounding()
+    }
+
+    #[test]
+    fn test_nest() {
+        let parts = vec![part(vec![square(50., 50., 10.)], 3), part(vec![square(0., 0., 20.)], 1)];
+        let mut options = NestOptions::new(35., 100.);
+        options.spacing = 1.;
+
+        let layout = nest(&parts, &options);
+        assert_eq!(layout.sheets.len(), 1);
+        assert!(layout.unplaced.is_empty());
+
+        let placements = &layout.sheets[0].placements;
+        assert_eq!(placements.len(), 4);
+        assert_eq!(placements[0].part, 1);
+        for (i, a) in placements.iter().enumerate() {
+            let a = placed_bounds(&parts, a);
+            assert!(a.min().0 >= 0. && a.max().0 <= 35.);
+            for b in &placements[(i + 1)..] {
+                assert!(!a.grow(1.).intersects(&placed_bounds(&parts, b), false));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sheets() {
+        let parts = vec![part(vec![square(0., 0., 10.)], 3), part(vec![square(0., 0., 30.)], 1)];
+        let layout = nest(&parts, &NestOptions::new(20., 10.));
+
+        assert_eq!(layout.sheets.len(), 2);
+        assert_eq!(layout.sheets[0].placements.len(), 2);
+        assert_eq!(layout.unplaced, vec![1]);
+    }
+
+    #[test]
+    fn test_part_in_part() {
+        let frame = part(vec![square(0., 0., 10.), square(1., 1., 8.)], 1);
+        let parts = vec![frame, part(vec![square(0., 0., 2.)], 4)];
+
+        let layout = nest(&parts, &NestOptions::new(100., 100.));
+        let frame = placed_bounds(&parts, &layout.sheets[0].placements[0]);
+        for placement in &layout.sheets[0].placements[1..] {
+            assert!(frame.contains(&placed_bounds(&parts, placement)));
+        }
+
+        let mut options = NestOptions::new(100., 100.);
+        options.part_in_part = false;
+        let layout = nest(&parts, &options);
+        for placement in &layout.sheets[0].placements[1..] {
+            assert!(!frame.intersects(&placed_bounds(&parts, placement), false));
+        }
+    }
+
+    #[test]
+    fn test_orient() {
+        let turned = OrientedRect {
+            center: Point(20., 20.),
+            width: 10.,
+            height: 2.,
+            angle: 30.,
+        };
+        let parts = vec![part(vec![turned.to_polygon()], 2)];
+        let mut options = NestOptions::new(3., 11.);
+        assert_eq!(nest(&parts, &options).unplaced.len(), 2);
+
+        options.orient = true;
+        let layout = nest(&parts, &options);
+        assert!(layout.unplaced.is_empty());
+        assert_eq!(layout.sheets.len(), 2);
+        let placement = &layout.sheets[0].placements[0];
+        assert!((placement.rotation - 60.).abs() < 1e-3);
+
+        let bounds = placed_bounds(&parts, placement);
+        assert!(bounds.min().0 > -1e-3 && bounds.max().0 < 3. + 1e-3);
+        assert!(bounds.min().1 > -1e-3 && bounds.max().1 < 11. + 1e-3);
+    }
+}
@@ -0,0 +1,226 @@
+use std::f32::consts::PI;
+use path::{Point, Polygon};
+
+/// Shape of the outline around the corners it is moved away from
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Join {
+    /// Sharp corners, squared off beyond the miter limit
+    Miter,
+    /// Arcs centred on the original corners, as cut by a round beam
+    #[default]
+    Round,
+    /// Corners squared off at the offset distance
+    Square,
+}
+
+#[derive(Debug, Clone)]
+pub struct OffsetOptions {
+    pub join: Join,
+    /// Largest ratio between the distance of a miter tip to its corner and
+    /// the offset distance
+    pub miter_limit: f32,
+    /// Largest distance between a round join and its flattened arc
+    pub arc_tolerance: f32,
+}
+
+impl Default for OffsetOptions {
+    fn default() -> Self {
+        OffsetOptions {
+            join: Join::default(),
+            miter_limit: 2.,
+            arc_tolerance: 0.01,
+        }
+    }
+}
+
+fn unit(a: Point, b: Point) -> Point {
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+    Point((b.0 - a.0) / length, (b.1 - a.1) / length)
+}
+
+fn along(p: Point, v: Point, distance: f32) -> Point {
+    Point(p.0 + v.0 * distance, p.1 + v.1 * distance)
+}
+
+/// Offsets a counter-clockwise outline by `delta`, outward if positive,
+/// without removing the loops this creates
+fn raw_offset(points: &[Point], delta: f32, options: &OffsetOptions) -> Vec<Point> {
+    let n = points.len();
+    let mut offset = Vec::with_capacity(n * 2);
+    for i in 0..n {
+        let (prev, p, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+        let (d1, d2) = (unit(prev, p), unit(p, next));
+        // Right hand normals, pointing out of counter-clockwise outlines
+        let (n1, n2) = (Point(d1.1, -d1.0), Point(d2.1, -d2.0));
+
+        let turn = d1.0 * d2.1 - d1.1 * d2.0;
+        let dot = d1.0 * d2.0 + d1.1 * d2.1;
+        if turn == 0. && dot > 0. {
+            offset.push(along(p, n1, delta));
+            continue;
+        }
+        if turn * delta < 0. {
+            // The outline is moved into the corner, its loop is removed later
+            offset.push(along(p, n1, delta));
+            offset.push(p);
+            offset.push(along(p, n2, delta));
+            continue;
+        }
+
+        let (distance, angle) = (delta.abs(), turn.atan2(dot));
+        let miter = 1. + n1.0 * n2.0 + n1.1 * n2.1;
+        match options.join {
+            Join::Miter if miter > 2. / (options.miter_limit * options.miter_limit) => {
+                let scale = delta / miter;
+                offset.push(Point(p.0 + (n1.0 + n2.0) * scale, p.1 + (n1.1 + n2.1) * scale));
+            }
+            Join::Round => {
+                let step = if options.arc_tolerance < distance {
+                    2. * (1. - options.arc_tolerance / distance).acos()
+                } else {
+                    PI / 2.
+                };
+                let steps = (angle.abs() / step).ceil().max(1.) as usize;
+                let start = n1.1.atan2(n1.0);
+                for k in 0..(steps + 1) {
+                    let a = start + angle * k as f32 / steps as f32;
+                    offset.push(along(p, Point(a.cos(), a.sin()), delta));
+                }
+            }
+            _ => {
+                // Square off the corner at the offset distance
+                let extent = distance * (angle.abs() / 4.).tan();
+                offset.push(along(along(p, n1, delta), d1, extent));
+                offset.push(along(along(p, n2, delta), d2, -extent));
+            }
+        }
+    }
+    offset
+}
+
+impl Polygon {
+    /// Moves the outline of a closed polygon by `delta` to the right of its
+    /// edges: outward for counter-clockwise outlines and into clockwise holes.
+    /// A negative `delta` moves it to the left. The polygon may split or
+    /// vanish, and the result is made of simple polygons turning the same way.
+    pub fn offset(&self, delta: f32, options: &OffsetOptions) -> Vec<Polygon> {
+        let mut outline = self.clone();
+        outline.points.dedup();
+        // Paths returning to their start before being closed
+        while outline.points.len() > 1 && outline.points.last() == outline.points.first() {
+            outline.points.pop();
+        }
+        if !self.closed || outline.points.len() < 3 || outline.signed_area() == 0. {
+            return Vec::new();
+        }
+        if delta == 0. {
+            return vec![outline];
+        }
+
+        // Work on the counter-clockwise region, grown or shrunk
+        let counter_clockwise = outline.signed_area() > 0.;
+        let delta = if counter_clockwise {
+            delta
+        } else {
+            outline.reverse();
+            -delta
+        };
+
+        let raw = Polygon {
+            closed: true,
+            points: raw_offset(&outline.points, delta, options),
+        };
+        let mut polygons = raw.split(|winding| winding > 0);
+        if !counter_clockwise {
+            for polygon in &mut polygons {
+                polygon.reverse();
+            }
+        }
+        polygons
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::XMLDoc;
+    use svg::Node;
+
+    fn square(size: f32) -> Polygon {
+        Polygon {
+            closed: true,
+            points: vec![Point(0., 0.), Point(size, 0.), Point(size, size), Point(0., size)],
+        }
+    }
+
+    fn area(polygons: &[Polygon]) -> f32 {
+        polygons.iter().map(|p| p.signed_area()).sum()
+    }
+
+    fn options(join: Join) -> OffsetOptions {
+        OffsetOptions {
+            join,
+            ..OffsetOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_joins() {
+        let miter = square(10.).offset(1., &options(Join::Miter));
+        assert_eq!(miter.len(), 1);
+        assert!((area(&miter) - 144.).abs() < 1e-3);
+
+        let round = square(10.).offset(1., &options(Join::Round));
+        assert!((area(&round) - (100. + 40. + PI)).abs() < 0.1);
+
+        let square_join = square(10.).offset(1., &options(Join::Square));
+        let expected = 144. - 4. * (3. - 2. * 2f32.sqrt());
+        assert!((area(&square_join) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_closed_twice() {
+        let doc = XMLDoc::parse("<svg><path d='M0 0 L10 0 10 10 0 10 0 0 z'/></svg>").unwrap();
+        let groups = Node::from_xml_doc(doc).unwrap().simplify();
+        let outline = &groups[0].polygons[0];
+        assert_eq!(outline.points.len(), 5);
+
+        let grown = outline.offset(1., &options(Join::Miter));
+        assert_eq!(grown.len(), 1);
+        assert!((area(&grown).abs() - 144.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_shrink() {
+        let inner = square(10.).offset(-1., &options(Join::Miter));
+        assert_eq!(inner.len(), 1);
+        assert!((area(&inner) - 64.).abs() < 1e-3);
+
+        assert!(square(10.).offset(-6., &options(Join::Round)).is_empty());
+    }
+
+    #[test]
+    fn test_hole() {
+        // Clockwise holes are moved inward and stay clockwise
+        let mut hole = square(10.);
+        hole.reverse();
+        let shrunk = hole.offset(1., &options(Join::Round));
+        assert_eq!(shrunk.len(), 1);
+        assert!((area(&shrunk) + 64.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_concave() {
+        // An U whose notch closes when grown
+        let u = Polygon {
+            closed: true,
+            points: vec![
+                Point(0., 0.), Point(5., 0.), Point(5., 5.), Point(3., 5.),
+                Point(3., 1.), Point(2., 1.), Point(2., 5.), Point(0., 5.),
+            ],
+        };
+        let grown = u.offset(1., &options(Join::Miter));
+        assert_eq!(grown.len(), 1);
+        assert!((area(&grown) - 49.).abs() < 1e-3);
+    }
+}
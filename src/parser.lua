@@ -22,6 +22,9 @@ typedef struct {
 typedef struct {
 	uint32_t size;
 	const Point *vertices;
+	bool hole;
+	bool closed;
+	int32_t parent;
 } Polygon;
 
 typedef struct {
@@ -31,14 +34,47 @@ typedef struct {
 	float w;
 	uint32_t size;
 	const Polygon *polygons;
+	const char *id;
+	const char *label;
+	const char *source;
+	float area;
+	float perimeter;
+	Point centroid;
+	Polygon hull;
+	Polygon min_rect;
+	bool part_in_part;
 } Group;
 
 typedef struct {
 	uint32_t size;
 	const Group *groups;
+	uint32_t warning_count;
+	const char **warnings;
 } Drawing;
 
+typedef struct {
+	uint32_t group;
+	uint32_t sheet;
+	float x;
+	float y;
+} Placed;
+
+typedef struct {
+	float width;
+	float height;
+	uint32_t sheets;
+	uint32_t size;
+	const Placed *placed;
+} Nesting;
+
 Drawing parse(const char* input);
+Drawing parse_dxf(const char* input);
+Drawing parse_json(const char* input);
+
+Nesting nest_drawing(const Drawing *drawing, float width, float height, float spacing);
+char *write_sheet(const Drawing *drawing, const Nesting *nesting, uint32_t sheet, const char *format, float scale, bool y_down);
+char *write_json(const Drawing *drawing, const Nesting *nesting, float scale);
+void free_string(char *s);
 
 void pretty_print(const char* input);
 ]]
@@ -46,8 +82,19 @@ void pretty_print(const char* input);
 local lib = ffi.load('target/release/' .. file)
 Parser.pretty_print = lib.pretty_print
 
-function Parser:parse(input)
-	local struct = lib.parse(input)
+-- Lit un document SVG, DXF si `format` vaut 'dxf' ou un travail enregistré
+-- si `format` vaut 'json', et renvoie les
+-- rectangles des pièces, le dessin dont elles viennent et les problèmes
+-- relevés dans le document
+function Parser:parse(input, format)
+	local struct
+	if format == 'dxf' then
+		struct = lib.parse_dxf(input)
+	elseif format == 'json' then
+		struct = lib.parse_json(input)
+	else
+		struct = lib.parse(input)
+	end
 	local rects = {}
 
 	-- print(struct.size)
@@ -57,6 +104,16 @@ function Parser:parse(input)
 		-- print("Group", group.x, group.y, group.h, group.w)
 		-- FIXME
 		local rect = Rectangle(group.x / 5, group.y / 5, group.h / 5, group.w / 5)
+		rect.id     = group.id ~= nil and ffi.string(group.id) or nil
+		rect.label  = group.label ~= nil and ffi.string(group.label) or nil
+		rect.source = ffi.string(group.source)
+		rect.group   = struct.groups + i
+		rect.index   = i
+		rect.originX = rect.x
+		rect.originY = rect.y
+		rect.area      = group.area / 25
+		rect.perimeter = group.perimeter / 5
+		rect.centroid  = { x = group.centroid.x / 5, y = group.centroid.y / 5 }
 		table.insert(rects, rect)
 
 		for j=0,group.size-1 do
@@ -68,11 +125,56 @@ function Parser:parse(input)
 				table.insert(vertices, polygon.vertices[k].x / 5)
 				table.insert(vertices, polygon.vertices[k].y / 5)
 			end
-			rect:attachShape(Polygon(unpack(vertices)))
+			local shape = Polygon(unpack(vertices))
+			shape.hole = polygon.hole
+			shape.closed = polygon.closed
+			rect:attachShape(shape)
 		end
 	end
 
-	return rects
+	local warnings = {}
+	for i=0,struct.warning_count-1 do
+		table.insert(warnings, ffi.string(struct.warnings[i]))
+	end
+
+	return rects, struct, warnings
+end
+
+-- Emboîte les pièces d'un dessin sur des plaques de `width` par `height`,
+-- espacées de `spacing`, et renvoie la position de chacune
+function Parser.nest(drawing, width, height, spacing)
+	local nesting = lib.nest_drawing(drawing, width * 5, height * 5, (spacing or 0) * 5)
+	local placed = {}
+	for i=0,nesting.size-1 do
+		local p = nesting.placed[i]
+		table.insert(placed, { group = p.group, sheet = p.sheet, x = p.x / 5, y = p.y / 5 })
+	end
+	return { struct = nesting, sheets = nesting.sheets, placed = placed }
+end
+
+-- Écrit une plaque de l'emboîtement en 'svg', 'dxf' ou 'gcode', une unité du
+-- dessin valant `scale` millimètres
+function Parser.write(drawing, nesting, sheet, format, scale, y_down)
+	local text = lib.write_sheet(drawing, nesting.struct, sheet, format, scale, y_down)
+	if text == nil then
+		return nil
+	end
+	local ret = ffi.string(text)
+	lib.free_string(text)
+	return ret
+end
+
+-- Écrit les pièces d'un dessin, et leur emboîtement s'il est donné, en un
+-- travail JSON à relire avec `Parser:parse`, une unité du dessin valant
+-- `scale` millimètres
+function Parser.json(drawing, nesting, scale)
+	local text = lib.write_json(drawing, nesting and nesting.struct or nil, scale)
+	if text == nil then
+		return nil
+	end
+	local ret = ffi.string(text)
+	lib.free_string(text)
+	return ret
 end
 
 return Parser
@@ -1,7 +1,6 @@
 use std::fmt;
 use std::str;
 use std::str::FromStr;
-use std::iter::FromIterator;
 use nom::{is_alphabetic, is_alphanumeric, multispace, IResult, IError, Needed};
 
 macro_rules! named_attr(
@@ -163,7 +162,7 @@ named!(
 );
 
 /// Used to store the XML declaration `<?xml … ?>`
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct XMLDecl {
     version: String,
     encoding: String,
@@ -204,7 +203,7 @@ named!(
 
 named!(comment_list<Vec<Comment>>, ws!(many0!(ws!(comment))));
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct XMLProlog {
     decl: Option<XMLDecl>,
     comments: Vec<Comment>,
@@ -227,7 +226,7 @@ named!(
 );
 
 /// Store the DOCTYPE
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct Doctype {
     name: String,
 }
@@ -243,7 +242,7 @@ named!(
 );
 
 /// Store a node attribute
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Attribute {
     pub name: String,
     pub value: String,
@@ -261,18 +260,18 @@ impl Attribute {
 /// Matches `key="value"`
 named!(
     attribute<Attribute>,
-    map_res!(
-        attr!(name, is_not!("<&")),
-        |(name, value)| -> Result<Attribute, str::Utf8Error> {
-            Ok(Attribute {
-                name,
-                value: String::from(str::from_utf8(value)?),
-            })
-        }
-    )
+    map_opt!(attr!(name, is_not!("<")), |(name, value)| {
+        attr_chars(value)
+            .to_full_result()
+            .ok()
+            .map(|value| Attribute { name, value })
+    })
 );
 
-#[derive(Debug, PartialEq)]
+/// Matches an attribute value (with entity refs converted)
+named!(attr_chars<String>, map!(many0!(complete!(chars)), |chunks: Vec<String>| chunks.concat()));
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Content {
     Comment(Comment),
     Element(Element),
@@ -296,7 +295,7 @@ impl Content {
 }
 
 /// A Node
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Element {
     pub name: String,
     pub attributes: Vec<Attribute>,
@@ -317,6 +316,49 @@ impl Element {
     }
 }
 
+impl Element {
+    fn serialize(&self, xml: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        xml.push_str(&format!("{}<{}", indent, self.name));
+        for attr in &self.attributes {
+            xml.push_str(&format!(r#" {}="{}""#, attr.name, escape(&attr.value)));
+        }
+
+        match self.children.as_slice() {
+            [] => xml.push_str(" />\n"),
+            [Content::Chars(ref s)] => {
+                xml.push_str(&format!(">{}</{}>\n", escape(s), self.name));
+            }
+            children => {
+                xml.push_str(">\n");
+                for child in children {
+                    match *child {
+                        Content::Comment(ref c) => xml.push_str(&format!("{}  <!--{}-->\n", indent, c.0)),
+                        Content::Element(ref e) => e.serialize(xml, depth + 1),
+                        Content::Chars(ref s) => xml.push_str(&format!("{}  {}\n", indent, escape(s))),
+                    }
+                }
+                xml.push_str(&format!("{}</{}>\n", indent, self.name));
+            }
+        }
+    }
+}
+
+/// Escapes a string to be written as character data or an attribute value
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 /// Matches a node (empty tag or tag pair)
 named!(
     element<Element>,
@@ -372,7 +414,10 @@ named!(
     )
 );
 
-named!(char_data<char>, none_of!("<&"));
+named!(
+    char_data<String>,
+    map_res!(map_res!(is_not!("<&"), str::from_utf8), FromStr::from_str)
+);
 
 /// Matches entity references, like `&amp;`
 named!(
@@ -387,13 +432,20 @@ named!(
     )
 );
 
-/// Matches a node value (with entity refs converted)
+/// Matches character data or an entity reference
+named!(
+    chars<String>,
+    alt!(char_data | map!(entity_ref, |c: char| c.to_string()))
+);
+
+/// Matches a node value (with entity refs converted), trailing whitespace
+/// aside
 named!(
     node_value<String>,
-    map!(ws!(many1!(alt!(char_data | entity_ref))), String::from_iter)
+    map!(many1!(complete!(chars)), |chunks: Vec<String>| String::from(chunks.concat().trim_end()))
 );
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct XMLDoc {
     prolog: XMLProlog,
     pub root: Element,
@@ -406,6 +458,33 @@ impl XMLDoc {
     }
 }
 
+impl XMLDoc {
+    /// Writes the document back as XML, indented, with comments kept
+    pub fn serialize(&self) -> String {
+        let mut xml = String::new();
+        if let Some(ref decl) = self.prolog.decl {
+            xml.push_str(&format!(r#"<?xml version="{}" encoding="{}""#, decl.version, decl.encoding));
+            if decl.standalone {
+                xml.push_str(r#" standalone="yes""#);
+            }
+            xml.push_str("?>\n");
+        }
+        for comment in &self.prolog.comments {
+            xml.push_str(&format!("<!--{}-->\n", comment.0));
+        }
+        if let Some(ref doctype) = self.prolog.doctype {
+            xml.push_str(&format!("<!DOCTYPE {}>\n", doctype.name));
+        }
+
+        self.root.serialize(&mut xml, 0);
+
+        for comment in &self.misc {
+            xml.push_str(&format!("<!--{}-->\n", comment.0));
+        }
+        xml
+    }
+}
+
 impl fmt::Display for XMLDoc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{:?}", self.prolog)?;
@@ -536,6 +615,16 @@ mod tests {
                 }
             )
         );
+        assert_eq!(
+            attribute(b"label=\"a &amp; b\""),
+            IResult::Done(
+                &b""[..],
+                Attribute {
+                    name: String::from("label"),
+                    value: String::from("a & b"),
+                }
+            )
+        );
     }
 
     #[test]
@@ -669,4 +758,26 @@ mod tests {
 
         assert_eq!(doc, IResult::Done(&b""[..], expected));
     }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"a <b> & "c""#), "a &lt;b&gt; &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_serialize() {
+        let source = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!-- Drawing -->
+<svg width=\"10mm\">
+  <g inkscape:label=\"A &amp; B\">
+    <path d=\"M 0 0 L 1 1\" />
+    <!-- Note -->
+    <text>x &lt; y</text>
+  </g>
+</svg>
+";
+        let doc = XMLDoc::parse(source).unwrap();
+        assert_eq!(doc.serialize(), source);
+        assert_eq!(XMLDoc::parse(&doc.serialize()).unwrap(), doc);
+    }
 }
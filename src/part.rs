@@ -0,0 +1,441 @@
+use offset::OffsetOptions;
+use path::{Bounding, BoundingBox, Polygon};
+use repair::Warning;
+use transform::Transform;
+use svg::{Grouping, PolygonGroup, Root};
+use SNAPPING;
+
+/// Rule deciding which regions enclosed by the contours of a path are filled
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+impl FillRule {
+    pub fn is_filled(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// A filled region: an outer boundary, turning counter-clockwise, and the
+/// holes cut into it
+#[derive(Debug, Clone)]
+pub struct Contour {
+    pub outer: Polygon,
+    pub holes: Vec<Hole>,
+}
+
+/// A hole, turning clockwise, and the filled regions lying inside it
+#[derive(Debug, Clone)]
+pub struct Hole {
+    pub polygon: Polygon,
+    pub islands: Vec<Contour>,
+}
+
+/// A part to cut, its polygons sorted in a containment tree
+#[derive(Debug, Clone)]
+pub struct Part {
+    pub id: Option<String>,
+    pub label: Option<String>,
+    pub source: String,
+    pub quantity: u32,
+    /// Whether other parts may be nested in the holes of this one
+    pub part_in_part: bool,
+    pub contours: Vec<Contour>,
+    /// Paths which do not bound any material: open paths, and closed ones
+    /// lying in a region without changing its filling
+    pub open: Vec<Polygon>,
+}
+
+impl From<PolygonGroup> for Part {
+    fn from(group: PolygonGroup) -> Self {
+        let (closed, mut open): (Vec<_>, Vec<_>) = group
+            .polygons
+            .into_iter()
+            .partition(|p| p.closed && p.points.len() >= 3 && p.signed_area() != 0.);
+        let (contours, inner) = Tree::new(closed, group.fill_rule).contours();
+        open.extend(inner);
+
+        Part {
+            id: group.id,
+            label: group.label,
+            source: group.source,
+            quantity: group.quantity,
+            part_in_part: group.part_in_part,
+            contours,
+            open,
+        }
+    }
+}
+
+impl Root {
+    /// The parts to cut from the document, grouped as asked: their contours
+    /// closed and self-intersections repaired. Returns the problems found
+    /// along the way.
+    pub fn to_parts(&self, grouping: &Grouping) -> (Vec<Part>, Vec<Warning>) {
+        let mut warnings = self.warnings();
+        let parts = self
+            .simplify_with(grouping)
+            .into_iter()
+            .map(|mut group| {
+                warnings.extend(group.close_contours(SNAPPING));
+                warnings.extend(group.repair());
+                Part::from(group)
+            })
+            .collect();
+        (parts, warnings)
+    }
+}
+
+impl Part {
+    /// Every closed polygon of the part, along with the index of the polygon
+    /// enclosing it, which is always listed first
+    pub fn polygons(&self) -> Vec<(&Polygon, Option<usize>)> {
+        let mut polygons = Vec::new();
+        for contour in &self.contours {
+            contour.collect(None, &mut polygons);
+        }
+        polygons
+    }
+
+    /// Moves the outlines of the part by `delta` away from its material: the
+    /// outer boundaries outward and the holes inward. Open paths are kept.
+    pub fn offset(&self, delta: f32, options: &OffsetOptions) -> Part {
+        let closed = self.polygons()
+            .into_iter()
+            .flat_map(|(polygon, _)| polygon.offset(delta, options))
+            .collect();
+        let (contours, inner) = Tree::new(closed, FillRule::NonZero).contours();
+        let mut open = self.open.clone();
+        open.extend(inner);
+
+        Part {
+            id: self.id.clone(),
+            label: self.label.clone(),
+            source: self.source.clone(),
+            quantity: self.quantity,
+            part_in_part: self.part_in_part,
+            contours,
+            open,
+        }
+    }
+
+    /// Moves every polygon of the part, which must not be mirrored so that
+    /// the contours keep turning the right way
+    pub fn transform(&mut self, transform: &Transform) {
+        for contour in &mut self.contours {
+            contour.transform(transform);
+        }
+        for polygon in &mut self.open {
+            polygon.transform(transform);
+        }
+    }
+
+    /// Every closed polygon of the part, along with whether it is a hole,
+    /// enclosing polygons first
+    pub fn cuts(&self) -> Vec<(&Polygon, bool)> {
+        let mut cuts: Vec<(&Polygon, bool)> = Vec::new();
+        for (polygon, parent) in self.polygons() {
+            // Polygons with an enclosing hole are islands, not holes
+            let hole = parent.is_some_and(|p| !cuts[p].1);
+            cuts.push((polygon, hole));
+        }
+        cuts
+    }
+
+    /// The holes of the outer contours, islands left aside
+    pub fn holes(&self) -> Vec<&Polygon> {
+        self.contours
+            .iter()
+            .flat_map(|contour| contour.holes.iter().map(|hole| &hole.polygon))
+            .collect()
+    }
+}
+
+impl Bounding for Part {
+    fn bounding(&self) -> BoundingBox {
+        let outlines: Vec<_> = self.contours.iter().map(|contour| contour.outer.bounding()).collect();
+        (outlines.bounding(), self.open.bounding()).bounding()
+    }
+}
+
+impl Contour {
+    fn transform(&mut self, transform: &Transform) {
+        self.outer.transform(transform);
+        for hole in &mut self.holes {
+            hole.polygon.transform(transform);
+            for island in &mut hole.islands {
+                island.transform(transform);
+            }
+        }
+    }
+
+    fn collect<'a>(&'a self, parent: Option<usize>, polygons: &mut Vec<(&'a Polygon, Option<usize>)>) {
+        let outer = polygons.len();
+        polygons.push((&self.outer, parent));
+        for hole in &self.holes {
+            let index = polygons.len();
+            polygons.push((&hole.polygon, Some(outer)));
+            for island in &hole.islands {
+                island.collect(Some(index), polygons);
+            }
+        }
+    }
+}
+
+/// Whether `outer` encloses `inner`, the two not crossing each other
+fn encloses(outer: &Polygon, inner: &Polygon) -> bool {
+    let (o, i) = (outer.bounding().to_rect(), inner.bounding().to_rect());
+    if i[0] < o[0] || i[1] < o[1] || i[0] + i[2] > o[0] + o[2] || i[1] + i[3] > o[1] + o[3] {
+        return false;
+    }
+
+    // Majority vote, as some vertices may lie on the other outline
+    let inside = inner.points.iter().filter(|&&p| outer.contains(p)).count();
+    inside * 2 > inner.points.len()
+}
+
+/// Containment tree of the closed polygons of a part
+struct Tree {
+    polygons: Vec<Option<Polygon>>,
+    areas: Vec<f32>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+    fill_rule: FillRule,
+    /// Polygons which do not change the filling of the region they lie in
+    inner: Vec<Polygon>,
+}
+
+impl Tree {
+    fn new(polygons: Vec<Polygon>, fill_rule: FillRule) -> Self {
+        let areas: Vec<f32> = polygons.iter().map(|p| p.signed_area()).collect();
+        let mut children = vec![Vec::new(); polygons.len()];
+        let mut roots = Vec::new();
+
+        for i in 0..polygons.len() {
+            // The parent is the smallest polygon enclosing this one
+            let parent = (0..polygons.len())
+                .filter(|&j| j != i && areas[j].abs() > areas[i].abs())
+                .filter(|&j| encloses(&polygons[j], &polygons[i]))
+                .min_by(|&a, &b| areas[a].abs().total_cmp(&areas[b].abs()));
+
+            match parent {
+                Some(parent) => children[parent].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        Tree {
+            polygons: polygons.into_iter().map(Some).collect(),
+            areas,
+            children,
+            roots,
+            fill_rule,
+            inner: Vec::new(),
+        }
+    }
+
+    /// Winding number change when entering the polygon
+    fn delta(&self, i: usize) -> i32 {
+        match self.fill_rule {
+            FillRule::NonZero if self.areas[i] < 0. => -1,
+            _ => 1,
+        }
+    }
+
+    /// Takes a polygon out of the tree, turning in the given direction
+    fn take(&mut self, i: usize, counter_clockwise: bool) -> Polygon {
+        let mut polygon = self.polygons[i].take().expect("polygon already taken");
+        if (self.areas[i] > 0.) != counter_clockwise {
+            polygon.reverse();
+        }
+        polygon
+    }
+
+    /// The filled regions, along with the polygons which bound none of them
+    fn contours(mut self) -> (Vec<Contour>, Vec<Polygon>) {
+        let roots = self.roots.clone();
+        let mut contours = Vec::new();
+        self.unfilled(&roots, 0, &mut contours);
+        (contours, self.inner)
+    }
+
+    /// Keeps a polygon which does not change the filling, as it is drawn
+    fn keep(&mut self, i: usize) {
+        let polygon = self.polygons[i].take().expect("polygon already taken");
+        self.inner.push(polygon);
+    }
+
+    /// Walks polygons lying in an unfilled region, collecting the outer boundaries
+    fn unfilled(&mut self, polygons: &[usize], winding: i32, contours: &mut Vec<Contour>) {
+        for &i in polygons {
+            let inside = winding + self.delta(i);
+            let children = self.children[i].clone();
+
+            if self.fill_rule.is_filled(inside) {
+                let mut contour = Contour {
+                    outer: self.take(i, true),
+                    holes: Vec::new(),
+                };
+                self.filled(&children, inside, &mut contour.holes);
+                contours.push(contour);
+            } else {
+                // Does not change the filling, its children stay in the same region
+                self.keep(i);
+                self.unfilled(&children, inside, contours);
+            }
+        }
+    }
+
+    /// Walks polygons lying in a filled region, collecting the holes
+    fn filled(&mut self, polygons: &[usize], winding: i32, holes: &mut Vec<Hole>) {
+        for &i in polygons {
+            let inside = winding + self.delta(i);
+            let children = self.children[i].clone();
+
+            if self.fill_rule.is_filled(inside) {
+                // Does not change the filling, its children stay in the same region
+                self.keep(i);
+                self.filled(&children, inside, holes);
+            } else {
+                let mut hole = Hole {
+                    polygon: self.take(i, false),
+                    islands: Vec::new(),
+                };
+                self.unfilled(&children, inside, &mut hole.islands);
+                holes.push(hole);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::XMLDoc;
+    use path::Point;
+    use svg::{Grouping, Node};
+
+    fn square(x: f32, y: f32, size: f32, ccw: bool) -> Polygon {
+        let mut points = vec![
+            Point(x, y),
+            Point(x + size, y),
+            Point(x + size, y + size),
+            Point(x, y + size),
+        ];
+        if !ccw {
+            points.reverse();
+        }
+        Polygon {
+            closed: true,
+            points,
+        }
+    }
+
+    fn part(polygons: Vec<Polygon>, fill_rule: FillRule) -> Part {
+        Part::from(PolygonGroup {
+            id: None,
+            label: None,
+            source: String::new(),
+            quantity: 1,
+            part_in_part: true,
+            fill_rule,
+            polygons,
+        })
+    }
+
+    #[test]
+    fn test_even_odd() {
+        let nested = part(
+            vec![
+                square(2., 2., 2., true),
+                square(0., 0., 10., true),
+                square(1., 1., 6., true),
+                square(20., 0., 1., false),
+            ],
+            FillRule::EvenOdd,
+        );
+
+        assert_eq!(nested.contours.len(), 2);
+        let frame = &nested.contours[0];
+        assert_eq!(frame.outer.signed_area(), 100.);
+        assert_eq!(frame.holes.len(), 1);
+        assert_eq!(frame.holes[0].polygon.signed_area(), -36.);
+        assert_eq!(frame.holes[0].islands.len(), 1);
+        assert_eq!(frame.holes[0].islands[0].outer.signed_area(), 4.);
+        assert_eq!(nested.contours[1].outer.signed_area(), 1.);
+
+        let parents: Vec<_> = nested.polygons().iter().map(|&(_, parent)| parent).collect();
+        assert_eq!(parents, vec![None, Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn test_non_zero() {
+        // Same orientation: the inner square does not cut a hole, but is kept
+        let same = part(
+            vec![square(0., 0., 10., true), square(1., 1., 6., true)],
+            FillRule::NonZero,
+        );
+        assert_eq!(same.contours.len(), 1);
+        assert!(same.contours[0].holes.is_empty());
+        assert_eq!(same.open.len(), 1);
+        assert_eq!(same.open[0].signed_area(), 36.);
+
+        let opposite = part(
+            vec![square(0., 0., 10., false), square(1., 1., 6., true)],
+            FillRule::NonZero,
+        );
+        assert_eq!(opposite.contours.len(), 1);
+        assert_eq!(opposite.contours[0].holes.len(), 1);
+        assert_eq!(opposite.contours[0].outer.signed_area(), 100.);
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let doc = XMLDoc::parse(
+            "<svg><g><g>
+                <path d='M0 0L10 0 10 10 0 10z'/>
+                <path d='M2 2L8 2 8 8 2 8z'/>
+            </g></g></svg>",
+        ).unwrap();
+        let groups = Node::from_xml_doc(doc).unwrap().simplify();
+        let polygons: usize = groups.iter().map(|g| g.polygons.len()).sum();
+        let parts: Vec<Part> = groups.into_iter().map(Part::from).collect();
+        let kept: usize = parts.iter().map(|p| p.polygons().len() + p.open.len()).sum();
+        assert_eq!((polygons, kept), (2, 2));
+    }
+
+    #[test]
+    fn test_to_parts() {
+        let doc = XMLDoc::parse(
+            "<svg>
+                <path d='M0 0L5 0.01 10 0 10 10 0 10z'/>
+                <path d='M20 0L30 0 30 10'/>
+                <path data-quantity='some' d='M0 20L10 20 0 30 10 30z'/>
+            </svg>",
+        ).unwrap();
+        let (parts, warnings) = Node::from_xml_doc(doc).unwrap().to_parts(&Grouping::Path);
+        assert_eq!(parts.len(), 3);
+        // Outlines are kept as drawn, only nesting simplifies them
+        assert_eq!(parts[0].contours[0].outer.points.len(), 5);
+        assert_eq!(parts[2].quantity, 0);
+        assert_eq!(parts[2].contours.len(), 2);
+
+        let sources: Vec<_> = warnings.iter().map(|w| w.source.as_str()).collect();
+        assert_eq!(sources, vec!["/svg/path[3]", "/svg/path[2]", "/svg/path[3]"]);
+    }
+
+    #[test]
+    fn test_open() {
+        let mut line = square(0., 0., 1., true);
+        line.closed = false;
+        let open = part(vec![line], FillRule::NonZero);
+        assert!(open.contours.is_empty());
+        assert_eq!(open.open.len(), 1);
+    }
+}
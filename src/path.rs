@@ -3,6 +3,7 @@ use std::str::FromStr;
 use std::fmt;
 use std::f32;
 use nom::{is_digit, space, IError};
+use transform::Transform;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Point(pub f32, pub f32);
@@ -39,6 +40,32 @@ impl Point {
             f32::max(self.1, other.1)
         )
     }
+
+    /// Cross product of `(a - self)` and `(b - self)`, positive if the three
+    /// points turn counter-clockwise
+    pub fn cross(self, a: Point, b: Point) -> f32 {
+        (a.0 - self.0) * (b.1 - self.1) - (a.1 - self.1) * (b.0 - self.0)
+    }
+}
+
+/// Whether the segments `[a1, a2]` and `[b1, b2]` intersect, touching included
+pub fn segments_intersect(a1: Point, a2: Point, b1: Point, b2: Point) -> bool {
+    let d1 = b1.cross(b2, a1);
+    let d2 = b1.cross(b2, a2);
+    let d3 = a1.cross(a2, b1);
+    let d4 = a1.cross(a2, b2);
+
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.)) && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.)) {
+        return true;
+    }
+
+    let on_segment = |p: Point, q1: Point, q2: Point| {
+        p.0 >= f32::min(q1.0, q2.0) && p.0 <= f32::max(q1.0, q2.0)
+            && p.1 >= f32::min(q1.1, q2.1) && p.1 <= f32::max(q1.1, q2.1)
+    };
+
+    (d1 == 0. && on_segment(a1, b1, b2)) || (d2 == 0. && on_segment(a2, b1, b2))
+        || (d3 == 0. && on_segment(b1, a1, a2)) || (d4 == 0. && on_segment(b2, a1, a2))
 }
 
 #[derive(Debug, PartialEq)]
@@ -120,12 +147,72 @@ impl MoveTo {
 pub struct BoundingBox(Point, Point);
 
 impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        BoundingBox(min, max)
+    }
+
+    pub fn min(&self) -> Point {
+        self.0
+    }
+
+    pub fn max(&self) -> Point {
+        self.1
+    }
+
+    pub fn width(&self) -> f32 {
+        self.1 .0 - self.0 .0
+    }
+
+    pub fn height(&self) -> f32 {
+        self.1 .1 - self.0 .1
+    }
+
+    /// Whether the box does not hold any point
+    pub fn is_empty(&self) -> bool {
+        self.0 .0 > self.1 .0 || self.0 .1 > self.1 .1
+    }
+
+    pub fn grow(&self, margin: f32) -> BoundingBox {
+        BoundingBox(
+            Point(self.0 .0 - margin, self.0 .1 - margin),
+            Point(self.1 .0 + margin, self.1 .1 + margin),
+        )
+    }
+
+    pub fn translate(&self, dx: f32, dy: f32) -> BoundingBox {
+        let delta = Point(dx, dy);
+        BoundingBox(self.0.translate(delta), self.1.translate(delta))
+    }
+
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        self.0 .0 <= other.0 .0 && self.0 .1 <= other.0 .1
+            && other.1 .0 <= self.1 .0 && other.1 .1 <= self.1 .1
+    }
+
+    /// The four corners of the box
+    pub fn corners(&self) -> [Point; 4] {
+        [
+            self.0,
+            Point(self.1 .0, self.0 .1),
+            self.1,
+            Point(self.0 .0, self.1 .1),
+        ]
+    }
+
     pub fn to_rect(&self) -> [f32; 4] {
         let (p1, p2) = (self.0, self.1);
         let (x, y) = (p1.0, p1.1);
         let (h, w) = (p2.0 - p1.0, p2.1 - p1.1);
         [x, y, h, w]
     }
+
+    /// Whether the two boxes share some area, or only an edge or a corner
+    /// when `touching` is set
+    pub fn intersects(&self, other: &BoundingBox, touching: bool) -> bool {
+        let before = |a: f32, b: f32| a < b || touching && a == b;
+        before(self.0 .0, other.1 .0) && before(other.0 .0, self.1 .0)
+            && before(self.0 .1, other.1 .1) && before(other.0 .1, self.1 .1)
+    }
 }
 
 pub trait Bounding {
@@ -189,6 +276,58 @@ impl Polygon {
             *point = point.translate(Point(dx, dy));
         }
     }
+
+    pub fn transform(&mut self, transform: &Transform) {
+        for point in &mut self.points {
+            *point = transform.apply(*point);
+        }
+    }
+
+    /// Area of the polygon, positive if its points turn counter-clockwise
+    /// (in a Y-up coordinate system)
+    pub fn signed_area(&self) -> f32 {
+        let sum: f64 = self.edges()
+            .map(|(a, b)| f64::from(a.0) * f64::from(b.1) - f64::from(b.0) * f64::from(a.1))
+            .sum();
+        (sum / 2.) as f32
+    }
+
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Iterates over the edges of the polygon, including the closing one
+    pub fn edges<'a>(&'a self) -> impl Iterator<Item = (Point, Point)> + 'a {
+        let points = &self.points;
+        (0..points.len()).map(move |i| (points[i], points[(i + 1) % points.len()]))
+    }
+
+    /// Even-odd point in polygon test
+    pub fn contains(&self, p: Point) -> bool {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            if (a.1 > p.1) != (b.1 > p.1) && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0 {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// Whether the two outlines touch, cross or are nested in one another
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        if self.points.is_empty() || other.points.is_empty()
+            || !self.bounding().intersects(&other.bounding(), true)
+        {
+            return false;
+        }
+
+        if self.contains(other.points[0]) || other.contains(self.points[0]) {
+            return true;
+        }
+
+        self.edges()
+            .any(|(a1, a2)| other.edges().any(|(b1, b2)| segments_intersect(a1, a2, b1, b2)))
+    }
 }
 
 impl Bounding for Polygon {
@@ -67,8 +67,9 @@ function Rectangle:draw()
 	end
 end
 
+-- Aire de la pièce elle-même si connue, sinon celle du rectangle
 function Rectangle:getArea()
-	return self.width*self.height
+	return self.area or self.width*self.height
 end
 
 -- Prend une ou plusieurs shapes avec lesquelles on vérifie les collisions
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fmt;
+use part::FillRule;
+use path::{Point, Polygon};
+use svg::PolygonGroup;
+
+/// Parameters below which a crossing is taken as lying on a vertex
+const EPSILON: f32 = 1e-5;
+
+/// A problem found with a source element
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// Location of the source element in the document
+    pub source: String,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+/// Positions along `[a1, a2]` and `[b1, b2]` of the point where they cross,
+/// touching included. Parallel segments never cross.
+pub fn crossing(a1: Point, a2: Point, b1: Point, b2: Point) -> Option<(f32, f32)> {
+    let (r, s) = (Point(a2.0 - a1.0, a2.1 - a1.1), Point(b2.0 - b1.0, b2.1 - b1.1));
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom == 0. {
+        return None;
+    }
+
+    let (dx, dy) = (b1.0 - a1.0, b1.1 - a1.1);
+    let t = (dx * s.1 - dy * s.0) / denom;
+    let u = (dx * r.1 - dy * r.0) / denom;
+    if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+        Some((t, u))
+    } else {
+        None
+    }
+}
+
+/// Point at the position `t` along `[a, b]`, snapped to its ends
+fn along(a: Point, b: Point, t: f32) -> Option<Point> {
+    if t < EPSILON {
+        Some(a)
+    } else if t > 1. - EPSILON {
+        Some(b)
+    } else {
+        None
+    }
+}
+
+/// Whether the region on the left of the loop is the filled one, or None
+/// when both sides are alike or the loop has no length
+fn filled_side<F: Fn(Point) -> bool>(polygon: &Polygon, filled: F) -> Option<bool> {
+    let (left, right) = polygon.sides()?;
+    let (left, right) = (filled(left), filled(right));
+    if left == right {
+        None
+    } else {
+        Some(left)
+    }
+}
+
+impl Polygon {
+    /// Winding number of the polygon around `p`
+    pub fn winding(&self, p: Point) -> i32 {
+        let mut winding = 0;
+        for (a, b) in self.edges() {
+            if a.1 <= p.1 && b.1 > p.1 && a.cross(b, p) > 0. {
+                winding += 1;
+            } else if a.1 > p.1 && b.1 <= p.1 && a.cross(b, p) < 0. {
+                winding -= 1;
+            }
+        }
+        winding
+    }
+
+    /// The polygon without its zero-length edges, the last point of a path
+    /// returning to its start before being closed included
+    fn without_repeats(&self) -> Polygon {
+        let mut points = self.points.clone();
+        points.dedup();
+        if self.closed {
+            while points.len() > 1 && points.last() == points.first() {
+                points.pop();
+            }
+        }
+        Polygon {
+            closed: self.closed,
+            points,
+        }
+    }
+
+    /// The points where non-adjacent edges of a closed polygon cross or touch,
+    /// along with the edges and the positions along them. The polygon must
+    /// not have zero-length edges.
+    fn crossings(&self) -> Vec<(usize, f32, usize, f32, Point)> {
+        let n = self.points.len();
+        let mut crossings = Vec::new();
+        if !self.closed || n < 4 {
+            return crossings;
+        }
+
+        let edges: Vec<_> = self.edges().collect();
+        for i in 0..n {
+            for j in (i + 2)..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+
+                let ((a1, a2), (b1, b2)) = (edges[i], edges[j]);
+                if a1.0.max(a2.0) < b1.0.min(b2.0) || b1.0.max(b2.0) < a1.0.min(a2.0)
+                    || a1.1.max(a2.1) < b1.1.min(b2.1) || b1.1.max(b2.1) < a1.1.min(a2.1)
+                {
+                    continue;
+                }
+
+                if let Some((t, u)) = crossing(a1, a2, b1, b2) {
+                    let p = along(a1, a2, t)
+                        .or_else(|| along(b1, b2, u))
+                        .unwrap_or(Point(a1.0 + t * (a2.0 - a1.0), a1.1 + t * (a2.1 - a1.1)));
+                    crossings.push((i, t, j, u, p));
+                }
+            }
+        }
+        crossings
+    }
+
+    /// The points where the outline of a closed polygon crosses or touches
+    /// itself
+    pub fn self_intersections(&self) -> Vec<Point> {
+        self.without_repeats().crossings().into_iter().map(|(_, _, _, _, p)| p).collect()
+    }
+
+    pub fn is_simple(&self) -> bool {
+        self.self_intersections().is_empty()
+    }
+
+    /// Splits a self-intersecting polygon into simple polygons bounding the
+    /// regions filled according to the fill rule. Filled regions turn
+    /// counter-clockwise and the holes cut into them clockwise.
+    pub fn repair(&self, fill_rule: FillRule) -> Vec<Polygon> {
+        if self.is_simple() {
+            return vec![self.clone()];
+        }
+        self.split(|winding| fill_rule.is_filled(winding))
+    }
+
+    /// Splits a closed polygon at its self-intersections into simple polygons
+    /// bounding the regions whose winding number is deemed filled, turning
+    /// counter-clockwise around them and clockwise around their holes
+    pub fn split<F: Fn(i32) -> bool>(&self, filled: F) -> Vec<Polygon> {
+        self.split_with(|piece| filled_side(piece, |p| filled(self.winding(p))))
+    }
+
+    /// Splits a closed polygon at its self-intersections into simple loops,
+    /// keeping those for which `side` tells whether the filled region lies
+    /// on their left, turned to have it there
+    fn split_with<F: Fn(&Polygon) -> Option<bool>>(&self, side: F) -> Vec<Polygon> {
+        let polygon = self.without_repeats();
+        let crossings = polygon.crossings();
+
+        // Insert the crossings along the edges
+        let mut splits = vec![Vec::new(); polygon.points.len()];
+        for &(i, t, j, u, p) in &crossings {
+            splits[i].push((t, p));
+            splits[j].push((u, p));
+        }
+
+        let mut walk = Vec::new();
+        for (i, splits) in splits.iter_mut().enumerate() {
+            splits.sort_by(|a, b| a.0.total_cmp(&b.0));
+            walk.push(polygon.points[i]);
+            walk.extend(splits.iter().map(|&(_, p)| p));
+        }
+        walk.push(polygon.points[0]);
+
+        // Cut a loop out of the walk each time it comes back to a point
+        let mut ids: HashMap<(u32, u32), usize> = HashMap::new();
+        let mut stack: Vec<(usize, Point)> = Vec::new();
+        let mut loops: Vec<Vec<Point>> = Vec::new();
+        for p in walk {
+            let next = ids.len();
+            let id = *ids.entry((p.0.to_bits(), p.1.to_bits())).or_insert(next);
+            if let Some(k) = stack.iter().position(|&(other, _)| other == id) {
+                let mut points = vec![stack[k].1];
+                points.extend(stack.split_off(k + 1).into_iter().map(|(_, p)| p));
+                loops.push(points);
+            } else {
+                stack.push((id, p));
+            }
+        }
+
+        let mut polygons = Vec::new();
+        for mut points in loops {
+            points.dedup();
+
+            let mut polygon = Polygon { closed: true, points };
+            if polygon.points.len() < 3 || polygon.signed_area() == 0. {
+                continue;
+            }
+
+            // Keep the loops between a filled and an unfilled region, the
+            // filled one on the left
+            match side(&polygon) {
+                Some(true) => polygons.push(polygon),
+                Some(false) => {
+                    polygon.reverse();
+                    polygons.push(polygon);
+                }
+                None => {}
+            }
+        }
+        polygons
+    }
+
+    /// Points just on the left and on the right of the longest edge, or None
+    /// when no edge has a finite, non-zero length
+    pub fn sides(&self) -> Option<(Point, Point)> {
+        let (_, (a, b)) = self.edges()
+            .map(|(a, b)| ((b.0 - a.0).hypot(b.1 - a.1), (a, b)))
+            .filter(|&(length, _)| length.is_finite() && length > 0.)
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+
+        let middle = Point((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+        let normal = Point(-(b.1 - a.1) * 1e-3, (b.0 - a.0) * 1e-3);
+        Some((
+            Point(middle.0 + normal.0, middle.1 + normal.1),
+            Point(middle.0 - normal.0, middle.1 - normal.1),
+        ))
+    }
+}
+
+impl PolygonGroup {
+    /// Splits the self-intersecting polygons of the group into simple ones,
+    /// warning about each of them. The polygons which do not cross themselves
+    /// keep their orientation.
+    ///
+    /// The polygons are repaired together, the regions being filled according
+    /// to the winding number of the whole group, so that a self-intersecting
+    /// hole still turns the other way from its outline. The loops lying on the
+    /// same side of the rest of the group are kept as their own winding tells.
+    pub fn repair(&mut self) -> Vec<Warning> {
+        let fill_rule = self.fill_rule;
+        let polygons: Vec<Polygon> = self.polygons
+            .drain(..)
+            .map(|polygon| polygon.without_repeats())
+            .collect();
+        let winding = |p: Point| -> i32 {
+            polygons.iter().filter(|polygon| polygon.closed).map(|polygon| polygon.winding(p)).sum()
+        };
+
+        let mut warnings = Vec::new();
+        let mut repaired = Vec::with_capacity(polygons.len());
+        for polygon in &polygons {
+            let intersections = polygon.self_intersections();
+            if intersections.is_empty() {
+                repaired.push(polygon.clone());
+                continue;
+            }
+
+            let pieces = polygon.split_with(|piece| {
+                filled_side(piece, |p| fill_rule.is_filled(winding(p)))
+                    .or_else(|| filled_side(piece, |p| fill_rule.is_filled(polygon.winding(p))))
+            });
+            warnings.push(Warning {
+                source: self.source.clone(),
+                message: format!(
+                    "self-intersecting polygon near {} split into {} polygons",
+                    intersections[0],
+                    pieces.len()
+                ),
+            });
+            repaired.extend(pieces);
+        }
+
+        self.polygons = repaired;
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::XMLDoc;
+    use part::Part;
+    use svg::{Grouping, Node};
+
+    fn polygon(points: &[(f32, f32)]) -> Polygon {
+        Polygon {
+            closed: true,
+            points: points.iter().map(|&(x, y)| Point(x, y)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        assert!(square.is_simple());
+        assert_eq!(square.repair(FillRule::NonZero).len(), 1);
+    }
+
+    #[test]
+    fn test_bow_tie() {
+        let bow_tie = polygon(&[(0., 0.), (2., 2.), (2., 0.), (0., 2.)]);
+        assert_eq!(bow_tie.self_intersections(), vec![Point(1., 1.)]);
+
+        let polygons = bow_tie.repair(FillRule::NonZero);
+        assert_eq!(polygons.len(), 2);
+        for polygon in &polygons {
+            assert!(polygon.is_simple());
+            assert_eq!(polygon.signed_area(), 1.);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_loop() {
+        // Goes twice around the inner square, leaving out a corner
+        let twice = polygon(&[
+            (0., 0.), (4., 0.), (4., 4.), (0., 4.), (0., 1.),
+            (3., 1.), (3., 3.), (1., 3.), (1., 0.),
+        ]);
+        assert!(!twice.is_simple());
+
+        let polygons = twice.repair(FillRule::NonZero);
+        let area: f32 = polygons.iter().map(|p| p.signed_area()).sum();
+        assert_eq!(area, 15.);
+
+        let polygons = twice.repair(FillRule::EvenOdd);
+        let area: f32 = polygons.iter().map(|p| p.signed_area()).sum();
+        assert_eq!(area, 11.);
+        assert!(polygons.iter().all(|p| p.is_simple()));
+    }
+
+    #[test]
+    fn test_sides_nan() {
+        let square = polygon(&[(0., 0.), (2., 0.), (2., 2.), (0., 2.)]);
+        let (left, right) = square.sides().unwrap();
+        assert_eq!((square.winding(left), square.winding(right)), (1, 0));
+
+        // Edges reaching a vertex which is not a number are left aside
+        let broken = polygon(&[(0., 0.), (2., 0.), (::std::f32::NAN, 2.), (0., 2.)]);
+        let (left, right) = broken.sides().unwrap();
+        assert!([left.0, left.1, right.0, right.1].iter().all(|c| c.is_finite()));
+
+        let nan = polygon(&[(::std::f32::NAN, 0.), (2., ::std::f32::NAN)]);
+        assert_eq!(nan.sides(), None);
+        assert_eq!(polygon(&[(1., 1.), (1., 1.)]).sides(), None);
+    }
+
+    fn groups(svg: &str) -> Vec<PolygonGroup> {
+        let doc = XMLDoc::parse(svg).unwrap();
+        Node::from_xml_doc(doc).unwrap().simplify_with(&Grouping::Path)
+    }
+
+    #[test]
+    fn test_closed_twice() {
+        // Returns to its start before being closed, around a hole
+        let mut group = groups("<svg><path d='M0 0 L10 0 10 10 0 10 0 0 z M2 2 L2 8 8 8 8 2 z'/></svg>").remove(0);
+        assert!(group.repair().is_empty());
+        assert_eq!(group.polygons.len(), 2);
+        assert!(group.polygons[0].signed_area() * group.polygons[1].signed_area() < 0.);
+
+        let part = Part::from(group);
+        assert_eq!(part.contours.len(), 1);
+        assert_eq!(part.holes().len(), 1);
+    }
+
+    #[test]
+    fn test_crossing_hole() {
+        // A bow tie turning both ways inside an outline
+        let mut group = groups("<svg><path d='M0 0 L10 0 10 10 0 10 z M2 2 L6 6 6 2 2 6 z'/></svg>").remove(0);
+        assert_eq!(group.repair().len(), 1);
+        assert_eq!(group.polygons.len(), 3);
+
+        // The lobe turning the other way is a hole, the other one does not
+        // change the filling and is kept as drawn
+        let part = Part::from(group);
+        assert_eq!(part.contours.len(), 1);
+        assert_eq!(part.holes().len(), 1);
+        assert_eq!(part.open.len(), 1);
+    }
+}
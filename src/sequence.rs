@@ -0,0 +1,270 @@
+use arc::{Curve, Segment};
+use gcode::Cut;
+use path::Point;
+
+/// Lengths of a cutting program, in millimetres
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CutReport {
+    /// Length run with the tool on
+    pub cut_length: f32,
+    /// Length of the rapid moves, from the home position to the last cut
+    pub travel_length: f32,
+    /// Number of times the tool is turned on
+    pub pierces: usize,
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+/// The curve started as close as possible to `from`: at one of its vertices
+/// for closed curves, or from its nearest end for open ones
+fn enter(curve: &Curve, from: Point) -> Curve {
+    if !curve.closed {
+        let reverse = curve.reverse();
+        return if distance(from, reverse.start) < distance(from, curve.start) {
+            reverse
+        } else {
+            curve.clone()
+        };
+    }
+
+    // A whole circle may be started anywhere
+    if let [Segment::Arc { center, sweep, .. }] = curve.segments[..] {
+        let radius = distance(center, curve.start);
+        let d = distance(center, from);
+        if d == 0. {
+            return curve.clone();
+        }
+        let start = Point(
+            center.0 + (from.0 - center.0) * radius / d,
+            center.1 + (from.1 - center.1) * radius / d,
+        );
+        return Curve {
+            start,
+            segments: vec![Segment::Arc {
+                end: start,
+                center,
+                sweep,
+            }],
+            closed: true,
+        };
+    }
+
+    let nearest = (0..curve.segments.len()).min_by(|&a, &b| {
+        let (a, b) = (curve.segments[a].end(), curve.segments[b].end());
+        distance(from, a).total_cmp(&distance(from, b))
+    });
+    match nearest {
+        Some(k) => {
+            let mut segments = curve.segments[(k + 1)..].to_vec();
+            segments.extend_from_slice(&curve.segments[..(k + 1)]);
+            Curve {
+                start: curve.segments[k].end(),
+                segments,
+                closed: true,
+            }
+        }
+        None => curve.clone(),
+    }
+}
+
+/// Orders the cuts to shorten the rapid moves between them, starting from
+/// `home`, and picks where each one starts. A cut is always done before the
+/// one enclosing it.
+///
+/// The cuts are first chained to their nearest neighbour, then the order is
+/// improved by reversing runs of cuts (2-opt) while it gets shorter.
+pub fn sequence(cuts: &[Cut], home: Point) -> Vec<Cut> {
+    let n = cuts.len();
+    let mut children = vec![Vec::new(); n];
+    for (i, cut) in cuts.iter().enumerate() {
+        if let Some(parent) = cut.parent {
+            children[parent].push(i);
+        }
+    }
+
+    // Nearest neighbour, among the cuts whose enclosed cuts are all done
+    let mut waiting: Vec<usize> = children.iter().map(|c| c.len()).collect();
+    let mut done = vec![false; n];
+    let mut curves: Vec<Curve> = cuts.iter().map(|cut| cut.curve.clone()).collect();
+    let mut order = Vec::with_capacity(n);
+    let mut position = home;
+    for _ in 0..n {
+        let next = (0..n)
+            .filter(|&i| !done[i] && waiting[i] == 0)
+            .map(|i| (i, enter(&curves[i], position)))
+            .min_by(|(_, a), (_, b)| {
+                distance(position, a.start).total_cmp(&distance(position, b.start))
+            });
+        let (i, curve) = match next {
+            Some(next) => next,
+            // The enclosing cuts form a cycle: the rest is kept in order
+            None => break,
+        };
+
+        position = curve.end();
+        curves[i] = curve;
+        done[i] = true;
+        order.push(i);
+        if let Some(parent) = cuts[i].parent {
+            waiting[parent] -= 1;
+        }
+    }
+    order.extend((0..n).filter(|&i| !done[i]));
+
+    // 2-opt: reversing the run from i to j only changes the moves into and
+    // out of it, the open curves of the run being run the other way
+    let entry = |curves: &[Curve], order: &[usize], k: usize| {
+        if k == order.len() {
+            None
+        } else {
+            Some(curves[order[k]].start)
+        }
+    };
+    let exit = |curves: &[Curve], order: &[usize], k: usize| {
+        if k == 0 {
+            home
+        } else {
+            curves[order[k - 1]].end()
+        }
+    };
+    let mut rank = vec![0; n];
+    for (k, &i) in order.iter().enumerate() {
+        rank[i] = k;
+    }
+    let mut improved = true;
+    let mut passes = 0;
+    while improved && passes < 100 {
+        improved = false;
+        passes += 1;
+        for i in 0..n {
+            for j in (i + 1)..n {
+                // Reversing would cut a cut after the one enclosing it
+                if children[order[j]].iter().any(|&c| rank[c] >= i) {
+                    break;
+                }
+
+                let before = exit(&curves, &order, i);
+                let after = entry(&curves, &order, j + 1);
+                let (first, last) = (&curves[order[i]], &curves[order[j]]);
+                let old = distance(before, first.start) + after.map_or(0., |a| distance(last.end(), a));
+                let new = distance(before, last.end()) + after.map_or(0., |a| distance(first.start, a));
+                if new < old - 1e-4 {
+                    order[i..(j + 1)].reverse();
+                    for (k, &c) in order.iter().enumerate().take(j + 1).skip(i) {
+                        rank[c] = k;
+                        if !curves[c].closed {
+                            curves[c] = curves[c].reverse();
+                        }
+                    }
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    // The start points, picked again for the new order
+    let mut position = home;
+    order
+        .into_iter()
+        .map(|i| {
+            let curve = enter(&curves[i], position);
+            position = curve.end();
+            Cut {
+                parent: cuts[i].parent.map(|p| rank[p]),
+                curve,
+                ..cuts[i].clone()
+            }
+        })
+        .collect()
+}
+
+/// Measures the program running the cuts in order, from `home`
+pub fn report(cuts: &[Cut], home: Point) -> CutReport {
+    let mut position = home;
+    let mut report = CutReport::default();
+    for cut in cuts {
+        report.travel_length += distance(position, cut.curve.start);
+        report.cut_length += cut.curve.length();
+        report.pierces += 1;
+        position = cut.curve.end();
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gcode::CutKind;
+    use path::Polygon;
+
+    fn square(x: f32, y: f32, size: f32, parent: Option<usize>) -> Cut {
+        let points = vec![Point(x, y), Point(x + size, y), Point(x + size, y + size), Point(x, y + size)];
+        Cut {
+            placement: 0,
+            kind: if parent.is_some() { CutKind::Inner } else { CutKind::Outer },
+            parent,
+            curve: Curve::from(&Polygon { closed: true, points }),
+        }
+    }
+
+    fn mark(x: f32) -> Cut {
+        Cut {
+            placement: 0,
+            kind: CutKind::Engrave,
+            parent: None,
+            curve: Curve {
+                start: Point(x, 0.),
+                segments: Vec::new(),
+                closed: false,
+            },
+        }
+    }
+
+    #[test]
+    fn test_holes_first() {
+        // The outer boundary is the nearest, but holds the holes
+        let cuts = vec![square(0., 0., 10., None), square(6., 6., 2., Some(0)), square(2., 2., 2., Some(0))];
+        let ordered = sequence(&cuts, Point(0., 0.));
+        assert_eq!(ordered.len(), 3);
+        assert_eq!(ordered[0].curve.start, Point(2., 2.));
+        assert_eq!(ordered[1].curve.start, Point(6., 6.));
+        assert_eq!(ordered[2].curve.start, Point(10., 10.));
+        assert_eq!(ordered[0].parent, Some(2));
+        assert_eq!(ordered[1].parent, Some(2));
+        assert_eq!(ordered[2].parent, None);
+
+        let report = report(&ordered, Point(0., 0.));
+        assert_eq!(report.pierces, 3);
+        assert_eq!(report.cut_length, 56.);
+        assert!((report.travel_length - 10. * 2f32.sqrt()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_two_opt() {
+        // Nearest neighbour goes to 1, back to -2 and on to 5
+        let cuts = vec![mark(1.), mark(-2.), mark(5.)];
+        let ordered = sequence(&cuts, Point(0., 0.));
+        let xs: Vec<f32> = ordered.iter().map(|cut| cut.curve.start.0).collect();
+        assert_eq!(xs, vec![-2., 1., 5.]);
+        assert_eq!(report(&ordered, Point(0., 0.)).travel_length, 9.);
+        assert_eq!(report(&cuts, Point(0., 0.)).travel_length, 11.);
+    }
+
+    #[test]
+    fn test_open_ends() {
+        let line = |from: f32, to: f32| Cut {
+            curve: Curve {
+                start: Point(from, 0.),
+                segments: vec![Segment::Line(Point(to, 0.))],
+                closed: false,
+            },
+            ..mark(0.)
+        };
+        let ordered = sequence(&[line(10., 5.), line(4., 1.)], Point(0., 0.));
+        assert_eq!(ordered[0].curve.start, Point(1., 0.));
+        assert_eq!(ordered[1].curve.start, Point(5., 0.));
+        assert_eq!(report(&ordered, Point(0., 0.)).travel_length, 2.);
+    }
+}
@@ -171,4 +171,22 @@ function Sorter:compact()
 	end
 end
 
+-- Déplace les pièces à leur place dans l'emboîtement renvoyé par
+-- `Parser.nest`, les plaques étant empilées verticalement
+function Sorter:nest(nesting, height)
+	local byGroup = {}
+	for i,v in pairs(self.shapes) do
+		if v.index then
+			byGroup[v.index] = v
+		end
+	end
+
+	for i,placed in ipairs(nesting.placed) do
+		local shape = byGroup[placed.group]
+		local x = shape.originX + placed.x + border
+		local y = shape.originY + placed.y + placed.sheet * (height + border) + border
+		shape:moveTo(x, y, 2)
+	end
+end
+
 return Sorter
\ No newline at end of file
@@ -1,26 +1,172 @@
 use parser::{Content, Element, XMLDoc};
-use path::{comma_wsp, number, Bounding, BoundingBox, Path, Polygon};
+use part::FillRule;
+use path::{number, Bounding, BoundingBox, Path, Polygon};
+use repair::Warning;
+use transform::Transform;
+use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug)]
-pub struct Root(Vec<Node>);
+pub struct Root(pub Vec<Node>);
+
+/// Policy deciding which polygons form a part
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Grouping {
+    /// One part per top-level group or path within each layer
+    #[default]
+    TopLevel,
+    /// One part per path
+    Path,
+    /// One part per set of touching or nested outlines
+    Outline,
+    /// One part per value of the given attribute, set on an element or on
+    /// one of its ancestors. Elements without it fall back to `TopLevel`
+    Attribute(String),
+}
+
+/// A set of polygons forming a part, along with the element it comes from
+#[derive(Debug, Clone)]
+pub struct PolygonGroup {
+    /// `id` of the source element
+    pub id: Option<String>,
+    /// `inkscape:label` of the source element
+    pub label: Option<String>,
+    /// Location of the source element in the document, like `/svg/g[1]/path[2]`
+    pub source: String,
+    /// Number of copies of the part to cut
+    pub quantity: u32,
+    /// Whether other parts may be nested in the holes of this one
+    pub part_in_part: bool,
+    /// `fill-rule` of the paths, telling apart outlines from holes
+    pub fill_rule: FillRule,
+    pub polygons: Vec<Polygon>,
+}
+
+impl PolygonGroup {
+    fn new(meta: &Meta, fill_rule: FillRule, polygons: Vec<Polygon>) -> Self {
+        PolygonGroup {
+            id: meta.id().map(String::from),
+            label: meta.label().map(String::from),
+            source: meta.source.clone(),
+            quantity: meta.quantity().unwrap_or(0),
+            part_in_part: meta.part_in_part(),
+            fill_rule,
+            polygons,
+        }
+    }
+}
+
+impl Bounding for PolygonGroup {
+    fn bounding(&self) -> BoundingBox {
+        self.polygons.bounding()
+    }
+}
 
 impl Root {
-    pub fn simplify(&self) -> Vec<Vec<Polygon>> {
-        let root = if self.0.len() == 1 {
-            if let Node::Group(ref children) = self.0[0] {
-                children
-            } else {
-                &self.0
+    pub fn simplify(&self) -> Vec<PolygonGroup> {
+        self.simplify_with(&Grouping::default())
+    }
+
+    pub fn simplify_with(&self, grouping: &Grouping) -> Vec<PolygonGroup> {
+        match *grouping {
+            Grouping::TopLevel => self.top_level()
+                .into_iter()
+                .map(|child| {
+                    PolygonGroup::new(child.meta(), child.fill_rule(), child.clone().simplify())
+                })
+                .collect(),
+            Grouping::Path => {
+                let mut parts = Vec::new();
+                for child in &self.0 {
+                    child.collect_paths(&mut parts);
+                }
+                parts
             }
-        } else {
-            &self.0
-        };
+            Grouping::Outline => {
+                let mut polygons = Vec::new();
+                for child in &self.0 {
+                    child.collect_polygons(&mut polygons);
+                }
+                outlines(polygons)
+            }
+            Grouping::Attribute(ref name) => {
+                let mut parts = Vec::new();
+                let mut index = HashMap::new();
+                for child in self.top_level() {
+                    let mut rest = Vec::new();
+                    child.collect_by_attribute(name, &mut parts, &mut index, &mut rest);
+                    if !rest.is_empty() {
+                        parts.push(PolygonGroup::new(child.meta(), child.fill_rule(), rest));
+                    }
+                }
+                parts
+            }
+        }
+    }
 
-        root.iter().map(|child| child.clone().simplify()).collect()
+    /// Problems found in the attributes of the elements, like a
+    /// `data-quantity` which is not a number
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        for child in &self.0 {
+            child.collect_warnings(&mut warnings);
+        }
+        warnings
+    }
+
+    /// The nodes standing for a part each: the children of the layers, or of
+    /// the single top-level group of a document without layers
+    fn top_level(&self) -> Vec<&Node> {
+        if self.0.len() == 1 && !self.0[0].meta().layer {
+            if let Node::Group(_, ref children) = self.0[0] {
+                return children.iter().collect();
+            }
+        }
+
+        let mut nodes = Vec::new();
+        for child in &self.0 {
+            child.collect_top_level(&mut nodes);
+        }
+        nodes
     }
 }
 
+/// Splits a list of polygons in sets of overlapping outlines, each set being
+/// attributed to the path of its first polygon
+fn outlines(polygons: Vec<(&Meta, Polygon)>) -> Vec<PolygonGroup> {
+    // Union-find over the polygons indices
+    fn find(parents: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[i] = root;
+        root
+    }
+
+    let mut parents: Vec<usize> = (0..polygons.len()).collect();
+    for i in 0..polygons.len() {
+        for j in (i + 1)..polygons.len() {
+            if polygons[i].1.overlaps(&polygons[j].1) {
+                let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                parents[b] = a;
+            }
+        }
+    }
+
+    let mut parts: Vec<PolygonGroup> = Vec::new();
+    let mut index = HashMap::new();
+    for (i, (meta, polygon)) in polygons.into_iter().enumerate() {
+        let root = find(&mut parents, i);
+        let part = *index.entry(root).or_insert_with(|| {
+            parts.push(PolygonGroup::new(meta, meta.fill_rule, Vec::new()));
+            parts.len() - 1
+        });
+        parts[part].polygons.push(polygon);
+    }
+    parts
+}
+
 impl fmt::Display for Root {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Root")?;
@@ -31,105 +177,427 @@ impl fmt::Display for Root {
     }
 }
 
+/// Informations kept from the source element of a node
+#[derive(Debug, Clone, Default)]
+pub struct Meta {
+    /// Whether the element is an Inkscape layer (`inkscape:groupmode="layer"`)
+    pub layer: bool,
+    /// Location of the element in the document, like `/svg/g[1]/path[2]`
+    pub source: String,
+    /// Computed `fill-rule` of the element
+    pub fill_rule: FillRule,
+    /// Attributes not already consumed by the conversion
+    pub attributes: HashMap<String, String>,
+}
+
+impl Meta {
+    pub fn id(&self) -> Option<&str> {
+        self.attributes.get("id").map(String::as_str)
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.attributes.get("inkscape:label").map(String::as_str)
+    }
+
+    /// Number of copies asked for with a `data-quantity` attribute, or with a
+    /// label ending in a separate word `x12`, `x 12` or `×12`. Defaults to 1,
+    /// while 0 keeps the part out of the nesting. Fails on a `data-quantity`
+    /// which is not a number.
+    pub fn quantity(&self) -> Result<u32, Warning> {
+        let from_label = || {
+            let mut words = self.label()?.split_whitespace().rev();
+            let last = words.next()?;
+            let count = match (last.strip_prefix('x').or_else(|| last.strip_prefix('×')), words.next()) {
+                (Some(count), _) if !count.is_empty() => count,
+                (None, Some("x")) | (None, Some("×")) => last,
+                _ => return None,
+            };
+            count.parse().ok()
+        };
+
+        match self.attributes.get("data-quantity") {
+            Some(quantity) => quantity.trim().parse().map_err(|_| Warning {
+                source: self.source.clone(),
+                message: format!("invalid data-quantity \"{}\", the part is not nested", quantity),
+            }),
+            None => Ok(from_label().unwrap_or(1)),
+        }
+    }
+
+    /// Whether parts may be nested in the holes of this one, unless disabled
+    /// with `data-part-in-part="false"`
+    pub fn part_in_part(&self) -> bool {
+        self.attributes.get("data-part-in-part").map(|v| v.trim()) != Some("false")
+    }
+
+    fn from_element(node: &Element, ctx: &Context) -> Self {
+        let attributes = node.attributes
+            .iter()
+            .filter(|a| !["d", "style", "transform"].contains(&a.name.as_str()))
+            .map(|a| (a.name.clone(), a.value.clone()))
+            .collect();
+
+        Meta {
+            layer: attribute(node, "inkscape:groupmode") == Some("layer"),
+            source: ctx.sources.get(&(node as *const Element)).cloned().unwrap_or_default(),
+            fill_rule: ctx.fill_rule,
+            attributes,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Node {
-    Path(Vec<Polygon>),
-    Group(Vec<Node>),
+    Path(Meta, Vec<Polygon>),
+    Group(Meta, Vec<Node>),
+}
+
+/// Options used while converting a XML document to a SVG tree
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Also convert the elements hidden with `display:none` or `visibility:hidden`,
+    /// and the entities on the DXF layers turned off or frozen
+    pub include_hidden: bool,
+}
+
+/// State shared while walking the document
+struct Context<'a> {
+    options: ParseOptions,
+    /// Every element of the document having an `id`
+    ids: HashMap<&'a str, &'a Element>,
+    /// Location of every element of the document
+    sources: HashMap<*const Element, String>,
+    /// Ids of the elements being converted, used to break reference cycles
+    stack: Vec<&'a str>,
+    /// Computed `visibility` of the current element, inherited by its children
+    visible: bool,
+    /// Computed `fill-rule` of the current element, inherited by its children
+    fill_rule: FillRule,
+}
+
+impl<'a> Context<'a> {
+    fn new(root: &'a Element, options: &ParseOptions) -> Self {
+        let mut ids = HashMap::new();
+        let mut sources = HashMap::new();
+        index(root, format!("/{}", root.name), &mut ids, &mut sources);
+
+        Context {
+            options: options.clone(),
+            ids,
+            sources,
+            stack: Vec::new(),
+            visible: true,
+            fill_rule: FillRule::default(),
+        }
+    }
+
+    fn is_hidden(&self) -> bool {
+        !self.visible && !self.options.include_hidden
+    }
 }
 
-named!(
-    parse_translate<(f32, f32)>,
-    delimited!(
-        tag!("translate("),
-        separated_pair!(number, comma_wsp, number),
-        tag!(")")
-    )
-);
+/// Indexes the elements by `id`, and records their location in the document
+fn index<'a>(
+    node: &'a Element,
+    source: String,
+    ids: &mut HashMap<&'a str, &'a Element>,
+    sources: &mut HashMap<*const Element, String>,
+) {
+    if let Some(id) = attribute(node, "id") {
+        ids.entry(id).or_insert(node);
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for child in &node.children {
+        if let Content::Element(ref e) = *child {
+            let count = counts.entry(e.name.as_str()).or_insert(0);
+            *count += 1;
+            index(e, format!("{}/{}[{}]", source, e.name, count), ids, sources);
+        }
+    }
+
+    sources.insert(node as *const Element, source);
+}
 
-fn translate(node: &Element) -> Option<(f32, f32)> {
-    let attr = node.attributes
+pub fn attribute<'a>(node: &'a Element, name: &str) -> Option<&'a str> {
+    node.attributes
         .iter()
-        .find(|a| a.name.as_str() == "transform")
-        .map(|a| a.value.clone())?;
-    let val = parse_translate(attr.as_bytes()).to_full_result().ok()?;
-    Some(val)
+        .find(|a| a.name.as_str() == name)
+        .map(|a| a.value.as_str())
+}
+
+/// Reads a presentation property, from the `style` attribute first, then from
+/// the presentation attribute of the same name
+fn property<'a>(node: &'a Element, name: &str) -> Option<&'a str> {
+    let declared = attribute(node, "style").and_then(|style| {
+        style
+            .split(';')
+            .filter_map(|declaration| {
+                let mut parts = declaration.splitn(2, ':');
+                let key = parts.next()?.trim();
+                let value = parts.next()?.trim();
+                if key == name {
+                    Some(value)
+                } else {
+                    None
+                }
+            })
+            .next_back()
+    });
+
+    declared.or_else(|| attribute(node, name).map(str::trim))
+}
+
+pub fn transform(node: &Element) -> Option<Transform> {
+    Transform::parse(attribute(node, "transform")?).ok()
+}
+
+/// Reads a length attribute, ignoring its unit
+fn length(node: &Element, name: &str) -> Option<f32> {
+    let attr = attribute(node, name)?;
+    number(attr.trim().as_bytes()).to_full_result().ok()
+}
+
+/// Resolves the `href` (or legacy `xlink:href`) of a `<use>` element
+fn href(node: &Element) -> Option<&str> {
+    let href = attribute(node, "href").or_else(|| attribute(node, "xlink:href"))?;
+    href.strip_prefix('#')
 }
 
 impl Bounding for Node {
     fn bounding(&self) -> BoundingBox {
         match *self {
-            Node::Path(ref polygons) => polygons.bounding(),
-            Node::Group(ref nodes) => nodes.bounding(),
+            Node::Path(_, ref polygons) => polygons.bounding(),
+            Node::Group(_, ref nodes) => nodes.bounding(),
         }
     }
 }
 
 impl Node {
     pub fn from_xml_doc(doc: XMLDoc) -> Option<Root> {
+        Node::from_xml_doc_with_options(doc, &ParseOptions::default())
+    }
+
+    pub fn from_xml_doc_with_options(doc: XMLDoc, options: &ParseOptions) -> Option<Root> {
         if doc.root.name != String::from("svg") {
             return None;
         }
 
-        Some(Root(Node::list_from_children(doc.root.children)))
+        let mut ctx = Context::new(&doc.root, options);
+        Some(Root(Node::list_from_children(&doc.root.children, &mut ctx)))
     }
 
-    fn list_from_children(children: Vec<Content>) -> Vec<Self> {
+    fn list_from_children<'a>(children: &'a [Content], ctx: &mut Context<'a>) -> Vec<Self> {
         children
-            .into_iter()
-            .filter_map(|n| match n {
-                Content::Element(e) => Some(e),
+            .iter()
+            .filter_map(|n| match *n {
+                Content::Element(ref e) => Some(e),
                 _ => None,
             })
-            .filter_map(Node::from_xml_node)
+            .filter_map(|e| Node::from_xml_node(e, ctx))
             .collect()
     }
 
-    fn translate(&mut self, dx: f32, dy: f32) {
+    pub fn transform(&mut self, transform: &Transform) {
         match *self {
-            Node::Path(ref mut polygons) => for polygon in &mut *polygons {
-                polygon.translate(dx, dy);
+            Node::Path(_, ref mut polygons) => for polygon in &mut *polygons {
+                polygon.transform(transform);
             },
-            Node::Group(ref mut children) => for child in &mut *children {
-                child.translate(dx, dy);
+            Node::Group(_, ref mut children) => for child in &mut *children {
+                child.transform(transform);
             },
         }
     }
 
-    fn from_xml_node(xml_node: Element) -> Option<Self> {
-        let delta = translate(&xml_node);
-        let mut node = match xml_node.name.as_str() {
-            "g" => Node::Group(Node::list_from_children(xml_node.children)),
+    fn from_xml_node<'a>(xml_node: &'a Element, ctx: &mut Context<'a>) -> Option<Self> {
+        let id = attribute(xml_node, "id");
+        if let Some(id) = id {
+            if ctx.stack.contains(&id) {
+                return None;
+            }
+            ctx.stack.push(id);
+        }
+
+        // `display:none` hides the whole subtree, while `visibility` is
+        // inherited and may be overridden by the children
+        let displayed = property(xml_node, "display") != Some("none");
+        let visible = ctx.visible;
+        ctx.visible = match property(xml_node, "visibility") {
+            Some("hidden") | Some("collapse") => false,
+            Some("visible") => true,
+            _ => visible,
+        };
+        let fill_rule = ctx.fill_rule;
+        ctx.fill_rule = match property(xml_node, "fill-rule") {
+            Some("evenodd") => FillRule::EvenOdd,
+            Some("nonzero") => FillRule::NonZero,
+            _ => fill_rule,
+        };
+
+        let node = if displayed || ctx.options.include_hidden {
+            Node::convert(xml_node, ctx)
+        } else {
+            None
+        };
+
+        ctx.visible = visible;
+        ctx.fill_rule = fill_rule;
+        if id.is_some() {
+            ctx.stack.pop();
+        }
+
+        let mut node = node?;
+        if let Some(transform) = transform(xml_node) {
+            node.transform(&transform);
+        }
+
+        Some(node)
+    }
+
+    fn convert<'a>(xml_node: &'a Element, ctx: &mut Context<'a>) -> Option<Self> {
+        let meta = Meta::from_element(xml_node, ctx);
+        let node = match xml_node.name.as_str() {
+            "g" => {
+                let children = Node::list_from_children(&xml_node.children, ctx);
+                if children.is_empty() && ctx.is_hidden() {
+                    return None;
+                }
+                Node::Group(meta, children)
+            }
+            "path" if ctx.is_hidden() => return None,
             "path" => {
-                let attr = xml_node
-                    .attributes
-                    .iter()
-                    .find(|a| a.name.as_str() == "d")
-                    .map(|a| a.value.clone())
-                    .unwrap_or(String::new());
-
-                let path = Path::parse(attr.as_str()).ok()?;
-                Node::Path(path.draw())
+                let attr = attribute(xml_node, "d").unwrap_or("");
+                let path = Path::parse(attr).ok()?;
+                Node::Path(meta, path.draw())
             }
+            "use" => Node::Group(meta, vec![Node::from_use(xml_node, ctx)?]),
+            // `<defs>` and `<symbol>` are only rendered through `<use>`
             _ => return None,
         };
 
-        if let Some((dx, dy)) = delta {
-            node.translate(dx, dy);
+        Some(node)
+    }
+
+    /// Instantiates the element referenced by a `<use>`
+    fn from_use<'a>(xml_node: &'a Element, ctx: &mut Context<'a>) -> Option<Self> {
+        let target = *ctx.ids.get(href(xml_node)?)?;
+        let target_id = attribute(target, "id")?;
+        if ctx.stack.contains(&target_id) {
+            return None;
         }
 
-        Some(node)
+        let mut instance = if target.name.as_str() == "symbol" {
+            ctx.stack.push(target_id);
+            let children = Node::list_from_children(&target.children, ctx);
+            ctx.stack.pop();
+            Node::Group(Meta::from_element(target, ctx), children)
+        } else {
+            Node::from_xml_node(target, ctx)?
+        };
+
+        let x = length(xml_node, "x").unwrap_or(0.);
+        let y = length(xml_node, "y").unwrap_or(0.);
+        if x != 0. || y != 0. {
+            instance.transform(&Transform::translate(x, y));
+        }
+
+        Some(instance)
+    }
+
+    pub fn meta(&self) -> &Meta {
+        match *self {
+            Node::Path(ref meta, _) | Node::Group(ref meta, _) => meta,
+        }
+    }
+
+    /// `fill-rule` of the first path of the node
+    fn fill_rule(&self) -> FillRule {
+        match *self {
+            Node::Path(ref meta, _) => meta.fill_rule,
+            Node::Group(ref meta, ref children) => children
+                .first()
+                .map_or(meta.fill_rule, |child| child.fill_rule()),
+        }
     }
 
     fn simplify(self) -> Vec<Polygon> {
         match self {
-            Node::Path(polygons) => polygons,
-            Node::Group(nodes) => nodes.into_iter().flat_map(|node| node.simplify()).collect(),
+            Node::Path(_, polygons) => polygons,
+            Node::Group(_, nodes) => nodes.into_iter().flat_map(|node| node.simplify()).collect(),
+        }
+    }
+
+    /// Collects the nodes of a layer, descending into the sub-layers
+    fn collect_top_level<'a>(&'a self, nodes: &mut Vec<&'a Node>) {
+        match *self {
+            Node::Group(ref meta, ref children) if meta.layer => for child in children {
+                child.collect_top_level(nodes);
+            },
+            _ => nodes.push(self),
+        }
+    }
+
+    fn collect_warnings(&self, warnings: &mut Vec<Warning>) {
+        if let Err(warning) = self.meta().quantity() {
+            warnings.push(warning);
+        }
+        if let Node::Group(_, ref children) = *self {
+            for child in children {
+                child.collect_warnings(warnings);
+            }
+        }
+    }
+
+    fn collect_paths(&self, parts: &mut Vec<PolygonGroup>) {
+        match *self {
+            Node::Path(ref meta, ref polygons) => {
+                parts.push(PolygonGroup::new(meta, meta.fill_rule, polygons.clone()))
+            }
+            Node::Group(_, ref children) => for child in children {
+                child.collect_paths(parts);
+            },
+        }
+    }
+
+    /// Collects every polygon along with its path
+    fn collect_polygons<'a>(&'a self, polygons: &mut Vec<(&'a Meta, Polygon)>) {
+        match *self {
+            Node::Path(ref meta, ref p) => polygons.extend(p.iter().map(|p| (meta, p.clone()))),
+            Node::Group(_, ref children) => for child in children {
+                child.collect_polygons(polygons);
+            },
+        }
+    }
+
+    /// Adds the polygons of this node to the part named after the `name`
+    /// attribute, or to `rest` if neither the node or its ancestors have one
+    fn collect_by_attribute(
+        &self,
+        name: &str,
+        parts: &mut Vec<PolygonGroup>,
+        index: &mut HashMap<String, usize>,
+        rest: &mut Vec<Polygon>,
+    ) {
+        if let Some(value) = self.meta().attributes.get(name) {
+            let part = *index.entry(value.clone()).or_insert_with(|| {
+                parts.push(PolygonGroup::new(self.meta(), self.fill_rule(), Vec::new()));
+                parts.len() - 1
+            });
+            parts[part].polygons.extend(self.clone().simplify());
+            return;
+        }
+
+        match *self {
+            Node::Path(_, ref polygons) => rest.extend(polygons.iter().cloned()),
+            Node::Group(_, ref children) => for child in children {
+                child.collect_by_attribute(name, parts, index, rest);
+            },
         }
     }
 
     pub fn pretty_print(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
         match self {
-            &Node::Path(ref polygons) => writeln!(
+            &Node::Path(_, ref polygons) => writeln!(
                 f,
                 "{0:1$}Path ({2} polygons, {3:?})",
                 "",
@@ -137,8 +605,9 @@ impl Node {
                 polygons.len(),
                 polygons.bounding()
             ),
-            &Node::Group(ref children) => {
-                writeln!(f, "{0:1$}Group ({2:?})", "", depth * 2, children.bounding())?;
+            &Node::Group(ref meta, ref children) => {
+                let kind = if meta.layer { "Layer" } else { "Group" };
+                writeln!(f, "{0:1$}{2} ({3:?})", "", depth * 2, kind, children.bounding())?;
                 for child in children {
                     child.pretty_print(f, depth + 1)?
                 }
@@ -147,3 +616,179 @@ impl Node {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(svg: &str) -> Root {
+        let doc = XMLDoc::parse(svg).unwrap();
+        Node::from_xml_doc(doc).unwrap()
+    }
+
+    #[test]
+    fn test_use() {
+        let root = parse(
+            "<svg>
+                <defs>
+                    <path id='square' d='M 0 0 L 1 0 1 1 0 1 z' />
+                </defs>
+                <use href='#square' x='10' y='0' />
+                <use xlink:href='#square' transform='translate(0, 10)' />
+            </svg>",
+        );
+
+        assert_eq!(root.0.len(), 2);
+        assert_eq!(root.0[0].bounding().to_rect(), [10., 0., 1., 1.]);
+        assert_eq!(root.0[1].bounding().to_rect(), [0., 10., 1., 1.]);
+        assert_eq!(root.simplify().len(), 2);
+    }
+
+    #[test]
+    fn test_use_symbol() {
+        let root = parse(
+            "<svg>
+                <symbol id='bracket'>
+                    <path d='M 0 0 L 2 0 2 2 z' />
+                    <path d='M 3 0 L 4 0 4 2 z' />
+                </symbol>
+                <use href='#bracket' x='5' y='5' />
+            </svg>",
+        );
+
+        assert_eq!(root.0.len(), 1);
+        assert_eq!(root.0[0].bounding().to_rect(), [5., 5., 4., 2.]);
+    }
+
+    #[test]
+    fn test_hidden() {
+        let svg = "<svg>
+                <g inkscape:groupmode='layer' style='display:none'>
+                    <path d='M 0 0 L 1 0 1 1 z' />
+                </g>
+                <g visibility='hidden'>
+                    <path d='M 0 0 L 1 0 1 1 z' />
+                    <path style='fill:none;visibility:visible' d='M 5 0 L 6 0 6 1 z' />
+                </g>
+                <g style='visibility:hidden'>
+                    <path d='M 0 0 L 1 0 1 1 z' />
+                </g>
+                <path style='display : none' d='M 0 0 L 1 0 1 1 z' />
+            </svg>";
+
+        let root = parse(svg);
+        assert_eq!(root.0.len(), 1);
+        assert_eq!(root.0[0].bounding().to_rect(), [5., 0., 1., 1.]);
+
+        let doc = XMLDoc::parse(svg).unwrap();
+        let options = ParseOptions { include_hidden: true };
+        let root = Node::from_xml_doc_with_options(doc, &options).unwrap();
+        assert_eq!(root.0.len(), 4);
+    }
+
+    #[test]
+    fn test_grouping() {
+        let root = parse(
+            "<svg>
+                <g inkscape:groupmode='layer'>
+                    <path data-part='a' d='M 0 0 L 4 0 4 4 0 4 z M 1 1 L 2 1 2 2 z' />
+                    <g>
+                        <path data-part='a' d='M 10 0 L 11 0 11 1 z' />
+                        <path d='M 20 0 L 21 0 21 1 z' />
+                    </g>
+                </g>
+                <g inkscape:groupmode='layer'>
+                    <path d='M 30 0 L 31 0 31 1 z' />
+                    <g inkscape:groupmode='layer'>
+                        <path d='M 40 0 L 41 0 41 1 z M 50 0 L 51 0 51 1 z' />
+                    </g>
+                </g>
+            </svg>",
+        );
+
+        let sizes = |grouping: Grouping| -> Vec<usize> {
+            root.simplify_with(&grouping).iter().map(|p| p.polygons.len()).collect()
+        };
+
+        assert_eq!(sizes(Grouping::TopLevel), vec![2, 2, 1, 2]);
+        assert_eq!(sizes(Grouping::Path), vec![2, 1, 1, 1, 2]);
+        assert_eq!(sizes(Grouping::Outline), vec![2, 1, 1, 1, 1, 1]);
+        assert_eq!(
+            sizes(Grouping::Attribute(String::from("data-part"))),
+            vec![3, 1, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let root = parse(
+            "<svg>
+                <defs><path id='hole' d='M 0 0 L 1 0 1 1 z' /></defs>
+                <g inkscape:groupmode='layer' inkscape:label='Layer 1'>
+                    <path id='frame' inkscape:label='Frame' d='M 0 0 L 4 0 4 4 z' />
+                    <use id='copy' href='#hole' />
+                    <g><path d='M 0 0 L 4 0 4 4 z' /></g>
+                </g>
+            </svg>",
+        );
+
+        let groups = root.simplify();
+        assert_eq!(groups[0].id, Some(String::from("frame")));
+        assert_eq!(groups[0].label, Some(String::from("Frame")));
+        assert_eq!(groups[0].source, "/svg/g[1]/path[1]");
+        assert_eq!(groups[1].id, Some(String::from("copy")));
+        assert_eq!(groups[1].source, "/svg/g[1]/use[1]");
+        assert_eq!(groups[2].id, None);
+        assert_eq!(groups[2].source, "/svg/g[1]/g[1]");
+
+        let groups = root.simplify_with(&Grouping::Path);
+        assert_eq!(groups[1].id, Some(String::from("hole")));
+        assert_eq!(groups[1].source, "/svg/defs[1]/path[1]");
+        assert_eq!(groups[2].source, "/svg/g[1]/g[1]/path[1]");
+    }
+
+    #[test]
+    fn test_quantity() {
+        let root = parse(
+            "<svg>
+                <path data-quantity='12' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Bracket x3' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='x4' data-quantity='0' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Box' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Bracketx12' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Bracket ×5' d='M 0 0 L 4 0 4 4 z' />
+                <path data-quantity='many' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Bracket x 7' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Plate 100x200' d='M 0 0 L 4 0 4 4 z' />
+                <path inkscape:label='Bolt M6x20' d='M 0 0 L 4 0 4 4 z' />
+            </svg>",
+        );
+
+        let quantities: Vec<_> = root.simplify().iter().map(|g| g.quantity).collect();
+        assert_eq!(quantities, vec![12, 3, 0, 1, 1, 5, 0, 7, 1, 1]);
+
+        let warnings = root.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].source, "/svg/path[7]");
+    }
+
+    #[test]
+    fn test_use_cycle() {
+        let root = parse(
+            "<svg>
+                <g id='a'>
+                    <path d='M 0 0 L 1 0 1 1 z' />
+                    <use href='#b' />
+                </g>
+                <g id='b'>
+                    <use href='#a' x='1' />
+                </g>
+                <use href='#c' />
+                <use id='c' href='#c' />
+            </svg>",
+        );
+
+        assert_eq!(root.simplify().len(), 2);
+        assert_eq!(root.0[1].bounding().to_rect(), [1., 0., 1., 1.]);
+    }
+}
@@ -0,0 +1,233 @@
+use arc::{Curve, Segment};
+use gcode::{Cut, CutKind};
+use path::Point;
+
+/// Shape of the moves leading into and out of a contour
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LeadShape {
+    /// A line square to the contour
+    #[default]
+    Line,
+    /// A quarter circle, tangent to the contour
+    Arc,
+}
+
+/// Options of the moves added around the contours, in millimetres
+#[derive(Debug, Clone, Default)]
+pub struct ToolpathOptions {
+    pub lead_shape: LeadShape,
+    /// Length of the line, or radius of the arc, pierced away from the
+    /// contour and cut up to its start. None when zero
+    pub lead_in: f32,
+    /// Length of the line, or radius of the arc, cut past the end of the
+    /// contour. None when zero
+    pub lead_out: f32,
+    /// Length of the bridges left uncut along the outer contours, holding
+    /// the parts to the sheet. None when zero
+    pub tab_width: f32,
+    /// Length of contour between two bridges
+    pub tab_spacing: f32,
+}
+
+/// Unit vector pointing away from the material at the start of the segment
+/// running from `start`, or at its end
+fn normal(cut: &Cut, start: Point, segment: &Segment, at_end: bool) -> Point {
+    let t = segment.direction(start, if at_end { 1. } else { 0. });
+    // Left of the direction lies inside a counter-clockwise contour: the
+    // hole itself for inner contours, the part for outer ones
+    let inside = cut.curve.signed_area().signum();
+    let side = if cut.kind == CutKind::Inner { inside } else { -inside };
+    Point(-t.1 * side, t.0 * side)
+}
+
+/// The move onto the contour at `to`, from `length` away along `normal`,
+/// heading towards `direction` when it gets there
+fn lead(shape: LeadShape, to: Point, direction: Point, normal: Point, length: f32) -> Curve {
+    match shape {
+        LeadShape::Line => Curve {
+            start: Point(to.0 + normal.0 * length, to.1 + normal.1 * length),
+            segments: vec![Segment::Line(to)],
+            closed: false,
+        },
+        LeadShape::Arc => {
+            let center = Point(to.0 + normal.0 * length, to.1 + normal.1 * length);
+            // Turning from the lead direction to the contour
+            let sweep = (direction.0 * normal.1 - direction.1 * normal.0).signum() * ::std::f32::consts::FRAC_PI_2;
+            Curve {
+                start: Point(center.0 - direction.0 * length, center.1 - direction.1 * length),
+                segments: vec![Segment::Arc { end: to, center, sweep }],
+                closed: false,
+            }
+        }
+    }
+}
+
+/// Joins the curves, each starting where the previous one ends
+fn join(curves: &[Curve]) -> Curve {
+    Curve {
+        start: curves[0].start,
+        segments: curves.iter().flat_map(|curve| curve.segments.iter().cloned()).collect(),
+        closed: false,
+    }
+}
+
+/// The runs of the contour cut with the tool on, leaving the bridges
+fn runs(cut: &Cut, options: &ToolpathOptions) -> Vec<Curve> {
+    let length = cut.curve.length();
+    let count = if cut.kind == CutKind::Outer && options.tab_width > 0. && options.tab_spacing > 0. {
+        (length / options.tab_spacing).floor() as usize
+    } else {
+        0
+    };
+    if count == 0 || options.tab_width >= length / count as f32 {
+        return vec![cut.curve.clone()];
+    }
+
+    // Bridges in the middle of each stretch, away from the leads
+    let stretch = length / count as f32;
+    let mut from = 0.;
+    let mut runs = Vec::with_capacity(count + 1);
+    for k in 0..count {
+        let middle = (k as f32 + 0.5) * stretch;
+        runs.push(cut.curve.slice(from, middle - options.tab_width / 2.));
+        from = middle + options.tab_width / 2.;
+    }
+    runs.push(cut.curve.slice(from, length));
+    runs
+}
+
+/// Adds the leads and bridges to the contours, which are then cut as open
+/// curves, a contour with bridges being split into several cuts. The parent
+/// of a cut becomes the first cut of its enclosing contour.
+pub fn toolpath(cuts: &[Cut], options: &ToolpathOptions) -> Vec<Cut> {
+    let mut first = Vec::with_capacity(cuts.len());
+    let mut pieces: Vec<Vec<Curve>> = Vec::with_capacity(cuts.len());
+    let mut n = 0;
+    for cut in cuts {
+        let mut runs = runs(cut, options);
+        let curve = &cut.curve;
+        if cut.kind != CutKind::Engrave && curve.closed && !curve.segments.is_empty() {
+            if options.lead_in > 0. {
+                let segment = &curve.segments[0];
+                let normal = normal(cut, curve.start, segment, false);
+                let direction = segment.direction(curve.start, 0.);
+                let lead = lead(options.lead_shape, curve.start, direction, normal, options.lead_in);
+                runs[0] = join(&[lead, runs[0].clone()]);
+            }
+            if options.lead_out > 0. {
+                let m = curve.segments.len();
+                let start = if m > 1 { curve.segments[m - 2].end() } else { curve.start };
+                let segment = &curve.segments[m - 1];
+                let normal = normal(cut, start, segment, true);
+                let direction = segment.direction(start, 1.);
+                // A lead into the end of the contour, run backwards
+                let back = Point(-direction.0, -direction.1);
+                let lead = lead(options.lead_shape, curve.end(), back, normal, options.lead_out).reverse();
+                let last = runs.len() - 1;
+                runs[last] = join(&[runs[last].clone(), lead]);
+            }
+        }
+
+        first.push(n);
+        n += runs.len();
+        pieces.push(runs);
+    }
+
+    cuts.iter()
+        .zip(pieces)
+        .flat_map(|(cut, runs)| {
+            let parent = cut.parent.map(|p| first[p]);
+            runs.into_iter().map(move |curve| Cut {
+                placement: cut.placement,
+                kind: cut.kind,
+                parent,
+                curve,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use path::Polygon;
+
+    fn square(x: f32, size: f32, kind: CutKind, parent: Option<usize>) -> Cut {
+        let points = vec![Point(x, x), Point(x + size, x), Point(x + size, x + size), Point(x, x + size)];
+        Cut {
+            placement: 0,
+            kind,
+            parent,
+            curve: Curve::from(&Polygon { closed: true, points }),
+        }
+    }
+
+    fn assert_close(a: Point, b: Point) {
+        assert!((a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_line_leads() {
+        let cuts = vec![square(4., 2., CutKind::Inner, Some(1)), square(0., 10., CutKind::Outer, None)];
+        let options = ToolpathOptions {
+            lead_in: 0.5,
+            lead_out: 0.25,
+            ..ToolpathOptions::default()
+        };
+        let paths = toolpath(&cuts, &options);
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].parent, Some(1));
+
+        // Inside the hole, and outside the outer boundary, square to the
+        // first and last edges
+        assert_close(paths[0].curve.start, Point(4., 4.5));
+        assert_close(paths[0].curve.end(), Point(4.25, 4.));
+        assert_close(paths[1].curve.start, Point(0., -0.5));
+        assert_close(paths[1].curve.end(), Point(-0.25, 0.));
+        assert_eq!(paths[1].curve.segments.len(), 6);
+        assert!(!paths[1].curve.closed);
+        assert!((paths[1].curve.length() - 40.75).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_arc_leads() {
+        let cuts = vec![square(0., 10., CutKind::Outer, None)];
+        let options = ToolpathOptions {
+            lead_shape: LeadShape::Arc,
+            lead_in: 1.,
+            lead_out: 1.,
+            ..ToolpathOptions::default()
+        };
+        let curve = &toolpath(&cuts, &options)[0].curve;
+        // Quarter circles outside, tangent to the first and last edges
+        assert_close(curve.start, Point(-1., -1.));
+        assert_close(curve.end(), Point(-1., -1.));
+        match (curve.segments[0], curve.segments[5]) {
+            (Segment::Arc { center: a, sweep: s, .. }, Segment::Arc { center: b, sweep: t, .. }) => {
+                assert_close(a, Point(0., -1.));
+                assert_close(b, Point(-1., 0.));
+                assert!(s < 0. && t < 0.);
+            }
+            _ => panic!("not arcs"),
+        }
+    }
+
+    #[test]
+    fn test_tabs() {
+        let cuts = vec![square(4., 2., CutKind::Inner, Some(1)), square(0., 10., CutKind::Outer, None)];
+        let options = ToolpathOptions {
+            tab_width: 1.,
+            tab_spacing: 10.,
+            ..ToolpathOptions::default()
+        };
+        let paths = toolpath(&cuts, &options);
+        // The hole is left whole, the boundary is cut in five runs
+        assert_eq!(paths.len(), 6);
+        assert!(paths[0].curve.closed);
+        assert!(paths[1..].iter().all(|cut| cut.kind == CutKind::Outer && cut.parent.is_none()));
+        let length: f32 = paths[1..].iter().map(|cut| cut.curve.length()).sum();
+        assert!((length - 36.).abs() < 1e-4);
+        assert_close(paths[1].curve.end(), Point(4.5, 0.));
+        assert_close(paths[2].curve.start, Point(5.5, 0.));
+    }
+}
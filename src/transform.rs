@@ -0,0 +1,222 @@
+use std::f32;
+use std::fmt;
+use nom::{multispace, IError};
+use path::{comma_wsp, number, Point};
+
+/// An affine transformation, stored as the SVG `matrix(a b c d e f)`:
+///
+/// ```text
+/// | a c e |
+/// | b d f |
+/// | 0 0 1 |
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Transform {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn matrix(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Transform { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Self {
+        Transform::matrix(1., 0., 0., 1., 0., 0.)
+    }
+
+    pub fn translate(tx: f32, ty: f32) -> Self {
+        Transform::matrix(1., 0., 0., 1., tx, ty)
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Self {
+        Transform::matrix(sx, 0., 0., sy, 0., 0.)
+    }
+
+    /// Rotation of `angle` degrees around the origin
+    pub fn rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Transform::matrix(cos, sin, -sin, cos, 0., 0.)
+    }
+
+    pub fn skew_x(angle: f32) -> Self {
+        Transform::matrix(1., 0., angle.to_radians().tan(), 1., 0., 0.)
+    }
+
+    pub fn skew_y(angle: f32) -> Self {
+        Transform::matrix(1., angle.to_radians().tan(), 0., 1., 0., 0.)
+    }
+
+    /// Parses a SVG `transform` attribute value
+    pub fn parse(str: &str) -> Result<Self, IError<u32>> {
+        transform_list(str.as_bytes()).to_full_result()
+    }
+
+    /// Composes two transformations, `other` being applied first
+    pub fn multiply(&self, other: &Transform) -> Transform {
+        Transform::matrix(
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        )
+    }
+
+    /// The transformation undoing this one, if it isn't degenerate
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0. {
+            return None;
+        }
+
+        Some(Transform::matrix(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        Point(
+            self.a * p.0 + self.c * p.1 + self.e,
+            self.b * p.0 + self.d * p.1 + self.f,
+        )
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::identity()
+    }
+
+    fn from_function(name: &[u8], args: &[f32]) -> Option<Self> {
+        let transform = match (name, args.len()) {
+            (b"matrix", 6) => Transform::matrix(args[0], args[1], args[2], args[3], args[4], args[5]),
+            (b"translate", 1) => Transform::translate(args[0], 0.),
+            (b"translate", 2) => Transform::translate(args[0], args[1]),
+            (b"scale", 1) => Transform::scale(args[0], args[0]),
+            (b"scale", 2) => Transform::scale(args[0], args[1]),
+            (b"rotate", 1) => Transform::rotate(args[0]),
+            (b"rotate", 3) => Transform::translate(args[1], args[2])
+                .multiply(&Transform::rotate(args[0]))
+                .multiply(&Transform::translate(-args[1], -args[2])),
+            (b"skewX", 1) => Transform::skew_x(args[0]),
+            (b"skewY", 1) => Transform::skew_y(args[0]),
+            _ => return None,
+        };
+
+        Some(transform)
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "matrix({} {} {} {} {} {})", self.a, self.b, self.c, self.d, self.e, self.f)
+    }
+}
+
+named!(
+    transform_args<Vec<f32>>,
+    delimited!(
+        terminated!(char!('('), opt!(multispace)),
+        separated_nonempty_list!(comma_wsp, number),
+        preceded!(opt!(multispace), char!(')'))
+    )
+);
+
+named!(
+    transform<Transform>,
+    map_opt!(
+        pair!(
+            alt!(
+                tag!("matrix") | tag!("translate") | tag!("scale") | tag!("rotate")
+                    | tag!("skewX") | tag!("skewY")
+            ),
+            preceded!(opt!(multispace), transform_args)
+        ),
+        |(name, args): (&[u8], Vec<f32>)| Transform::from_function(name, &args)
+    )
+);
+
+named!(
+    transform_list<Transform>,
+    map!(
+        delimited!(
+            opt!(complete!(multispace)),
+            many0!(terminated!(transform, opt!(complete!(alt!(comma_wsp | value!((), multispace)))))),
+            eof!()
+        ),
+        |transforms: Vec<Transform>| transforms
+            .iter()
+            .fold(Transform::identity(), |acc, t| acc.multiply(t))
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(p: Point, expected: Point) {
+        assert!(
+            (p.0 - expected.0).abs() < 1e-4 && (p.1 - expected.1).abs() < 1e-4,
+            "{} != {}",
+            p,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(Transform::parse("translate(0,2490.9448)"), Ok(Transform::translate(0., 2490.9448)));
+        assert_eq!(Transform::parse("translate(12)"), Ok(Transform::translate(12., 0.)));
+    }
+
+    #[test]
+    fn test_transform_list() {
+        let t = Transform::parse(" translate(10 20) scale(2)").unwrap();
+        assert_close(t.apply(Point(1., 1.)), Point(12., 22.));
+
+        let t = Transform::parse("rotate(90, 1 1),translate(1,0)").unwrap();
+        assert_close(t.apply(Point(1., 1.)), Point(1., 2.));
+    }
+
+    #[test]
+    fn test_matrix() {
+        let t = Transform::parse("matrix(1 0 0 -1 5 5)").unwrap();
+        assert_close(t.apply(Point(1., 2.)), Point(6., 3.));
+    }
+
+    #[test]
+    fn test_inverse() {
+        let t = Transform::parse("translate(3 4) rotate(30) scale(2 1)").unwrap();
+        let p = Point(5., -1.);
+        assert_close(t.inverse().unwrap().apply(t.apply(p)), p);
+        assert_eq!(Transform::scale(0., 1.).inverse(), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let t = Transform::translate(1.5, -2.).multiply(&Transform::scale(2., 3.));
+        assert_eq!(t.to_string(), "matrix(2 0 0 3 1.5 -2)");
+        assert_eq!(Transform::parse(&t.to_string()), Ok(t));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Transform::parse("rotate(1 2)").is_err());
+        assert!(Transform::parse("translate(1 2").is_err());
+    }
+}